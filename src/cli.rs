@@ -0,0 +1,473 @@
+use std::str::FromStr;
+
+use format::Format;
+use grid::generator::{Difficulty, PuzzleId, Symmetry};
+use grid::solver::Backend;
+
+pub const USAGE: &str = "\
+Usage: sudoku [COMMAND] [OPTIONS]
+
+Commands:
+    play [ID]           Play in the terminal, the default command
+        -d, --difficulty X  Start with a puzzle of this difficulty
+        -s, --seed N        Generator seed, to get the same puzzle again
+        -f, --file FILE     Play the first puzzle in FILE
+    solve [FILE]        Print the solution of every puzzle in the same format,
+                        failing if any has no solution or more than one,
+                        which are printed unsolved in their place
+        --solver NAME       backtracking or dlx, backtracking by default
+    generate            Print new puzzles, never the same one twice in disguise
+        -d, --difficulty X  Difficulty of the puzzles, medium by default
+        -r, --rating MIN-MAX
+                            Only keep puzzles with a rating score in the range,
+                            like 2.5-4, of any difficulty unless one is given
+        -n, --count N       How many to print, 1 by default
+        -s, --seed N        Seed of the first puzzle, counting up from there
+        -y, --symmetry X    none, rotational or mirror, none by default
+        -F, --format FORMAT line, csv or grid, csv by default
+        -o, --output FILE   Write the puzzles to FILE instead
+        --no-dedup          Allow the same puzzle more than once
+    rate [FILE]         Print how hard every puzzle is to solve by hand
+    validate [FILE]     Check that every puzzle has exactly one solution
+        --solver NAME       backtracking or dlx, backtracking by default
+    convert [FILE]      Print the puzzles in another format
+        -t, --to FORMAT     line, csv or grid
+    help                Show this message
+
+Puzzles are read from FILE, or standard input if it's missing or -, as
+81 character lines, 9 lines of comma separated digits, or 9 line grids,
+with ., 0 or _ for empty squares.
+Difficulties are very-easy, easy, medium, hard and fiendish.
+";
+
+/// What the program was asked to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Play {
+        id: Option<PuzzleId>,
+        difficulty: Option<Difficulty>,
+        file: Option<String>,
+    },
+    Solve {
+        file: Option<String>,
+        solver: Backend,
+    },
+    Generate(Generate),
+    Rate {
+        file: Option<String>,
+    },
+    Validate {
+        file: Option<String>,
+        solver: Backend,
+    },
+    Convert {
+        file: Option<String>,
+        to: Format,
+    },
+    Help,
+}
+
+/// What to generate, and where to write it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generate {
+    pub difficulty: Option<Difficulty>,
+    /// Range of rating scores to keep
+    pub rating: Option<(f32, f32)>,
+    pub count: usize,
+    /// Seed of the first puzzle, None for random ones
+    pub seed: Option<u32>,
+    pub symmetry: Symmetry,
+    pub format: Format,
+    /// File to write to, None for standard output
+    pub output: Option<String>,
+    /// Whether to leave out puzzles that are disguises of earlier ones
+    pub dedup: bool,
+}
+
+/// Parse the arguments, not including the program name
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let Some(first) = args.first() else {
+        return Ok(Command::Play {
+            id: None,
+            difficulty: None,
+            file: None,
+        });
+    };
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        return Ok(Command::Help);
+    }
+
+    let (name, rest) = if first.starts_with('-') || first.parse::<PuzzleId>().is_ok() {
+        // Plain `sudoku H-3f9a2` or `sudoku --seed 12` mean play
+        ("play", args)
+    } else {
+        (first.as_str(), &args[1..])
+    };
+
+    let command = match name {
+        "play" => {
+            let options = Options::parse(
+                rest,
+                &["-d", "--difficulty", "-s", "--seed", "-f", "--file"],
+                1,
+            )?;
+            let mut id = options
+                .positional(0)
+                .map(str::parse::<PuzzleId>)
+                .transpose()?;
+            let difficulty = options.value(&["-d", "--difficulty"], Difficulty::from_str)?;
+            let seed = options.value(&["-s", "--seed"], parse_seed)?;
+            let file = options.value(&["-f", "--file"], |file| Ok(file.to_string()))?;
+            if let Some(seed) = seed {
+                id = Some(PuzzleId {
+                    difficulty: difficulty.unwrap_or(Difficulty::Medium),
+                    seed,
+                });
+            }
+            Command::Play {
+                id,
+                difficulty,
+                file,
+            }
+        }
+        "solve" => {
+            let options = Options::parse(rest, &["--solver"], 1)?;
+            Command::Solve {
+                file: options.file(),
+                solver: options.solver()?,
+            }
+        }
+        "generate" => {
+            let options = Options::with_switches(
+                rest,
+                &[
+                    "-d",
+                    "--difficulty",
+                    "-r",
+                    "--rating",
+                    "-n",
+                    "--count",
+                    "-s",
+                    "--seed",
+                    "-y",
+                    "--symmetry",
+                    "-F",
+                    "--format",
+                    "-o",
+                    "--output",
+                ],
+                &["--no-dedup"],
+                0,
+            )?;
+            Command::Generate(Generate {
+                difficulty: options.value(&["-d", "--difficulty"], Difficulty::from_str)?,
+                rating: options.value(&["-r", "--rating"], parse_range)?,
+                count: options
+                    .value(&["-n", "--count"], |count| {
+                        count.parse().map_err(|_| format!("Invalid count: {count}"))
+                    })?
+                    .unwrap_or(1),
+                seed: options.value(&["-s", "--seed"], parse_seed)?,
+                symmetry: options
+                    .value(&["-y", "--symmetry"], Symmetry::from_str)?
+                    .unwrap_or(Symmetry::None),
+                format: options
+                    .value(&["-F", "--format"], Format::from_str)?
+                    .unwrap_or(Format::Csv),
+                output: options.value(&["-o", "--output"], |file| Ok(file.to_string()))?,
+                dedup: !options.switch("--no-dedup"),
+            })
+        }
+        "rate" => Command::Rate {
+            file: Options::parse(rest, &[], 1)?.file(),
+        },
+        "validate" => {
+            let options = Options::parse(rest, &["--solver"], 1)?;
+            Command::Validate {
+                file: options.file(),
+                solver: options.solver()?,
+            }
+        }
+        "convert" => {
+            let options = Options::parse(rest, &["-t", "--to"], 1)?;
+            Command::Convert {
+                file: options.file(),
+                to: options
+                    .value(&["-t", "--to"], Format::from_str)?
+                    .ok_or("convert needs a format to convert to, given with --to")?,
+            }
+        }
+        "help" => Command::Help,
+        _ => return Err(format!("Unknown command: {name}")),
+    };
+
+    Ok(command)
+}
+
+/// Seeds are written in decimal, or hex with a 0x prefix
+fn parse_seed(seed: &str) -> Result<u32, String> {
+    match seed.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => seed.parse(),
+    }
+    .map_err(|_| format!("Invalid seed: {seed}"))
+}
+
+/// A range of rating scores written like `2.5-4`
+fn parse_range(range: &str) -> Result<(f32, f32), String> {
+    let invalid = || format!("Invalid rating range: {range}");
+    let (min, max) = range.split_once('-').ok_or_else(invalid)?;
+    let min: f32 = min.trim().parse().map_err(|_| invalid())?;
+    let max: f32 = max.trim().parse().map_err(|_| invalid())?;
+    if min > max {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
+
+/// Arguments of a subcommand, split into options with values
+/// (`--name value` or `--name=value`), switches without values
+/// and positional arguments
+struct Options {
+    named: Vec<(String, String)>,
+    switches: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    /// Split the arguments, rejecting options not in `flags`
+    /// and more than `max_positional` positional arguments
+    fn parse(args: &[String], flags: &[&str], max_positional: usize) -> Result<Options, String> {
+        Options::with_switches(args, flags, &[], max_positional)
+    }
+
+    /// Like `parse`, also accepting the given switches
+    fn with_switches(
+        args: &[String],
+        flags: &[&str],
+        switches: &[&str],
+        max_positional: usize,
+    ) -> Result<Options, String> {
+        let mut named = vec![];
+        let mut on = vec![];
+        let mut positional = vec![];
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if switches.contains(&arg.as_str()) {
+                on.push(arg.clone());
+            } else if arg.starts_with('-') && arg != "-" {
+                let (name, value) = match arg.split_once('=') {
+                    Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+                    _ => (arg.as_str(), None),
+                };
+                if !flags.contains(&name) {
+                    return Err(format!("Unknown option: {name}"));
+                }
+                let value = match value {
+                    Some(value) => value,
+                    None => args
+                        .next()
+                        .ok_or_else(|| format!("Missing value for {arg}"))?
+                        .clone(),
+                };
+                named.push((name.to_string(), value));
+            } else if positional.len() < max_positional {
+                positional.push(arg.clone());
+            } else {
+                return Err(format!("Unexpected argument: {arg}"));
+            }
+        }
+
+        Ok(Options {
+            named,
+            switches: on,
+            positional,
+        })
+    }
+
+    /// The value of the last option with one of the given names
+    fn value<T, F>(&self, names: &[&str], parse: F) -> Result<Option<T>, String>
+    where
+        F: Fn(&str) -> Result<T, String>,
+    {
+        self.named
+            .iter()
+            .rev()
+            .find(|(name, _)| names.contains(&name.as_str()))
+            .map(|(_, value)| parse(value))
+            .transpose()
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|switch| switch == name)
+    }
+
+    fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(|arg| arg.as_str())
+    }
+
+    /// The solver picked with `--solver`, backtracking by default
+    fn solver(&self) -> Result<Backend, String> {
+        Ok(self
+            .value(&["--solver"], Backend::from_str)?
+            .unwrap_or(Backend::Backtracking))
+    }
+
+    /// The input file, where None means standard input, as does "-"
+    fn file(&self) -> Option<String> {
+        self.positional(0)
+            .filter(|&file| file != "-")
+            .map(|file| file.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Command, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        parse(&args)
+    }
+
+    fn play(id: Option<PuzzleId>, difficulty: Option<Difficulty>) -> Command {
+        Command::Play {
+            id,
+            difficulty,
+            file: None,
+        }
+    }
+
+    #[test]
+    fn plays_by_default() {
+        assert_eq!(parse_args(""), Ok(play(None, None)));
+        assert_eq!(parse_args("play"), Ok(play(None, None)));
+        assert_eq!(
+            parse_args("-d hard"),
+            Ok(play(None, Some(Difficulty::Hard)))
+        );
+    }
+
+    #[test]
+    fn plays_puzzle_ids_and_seeds() {
+        let id = PuzzleId {
+            difficulty: Difficulty::Hard,
+            seed: 0x3f9a2,
+        };
+        assert_eq!(parse_args("H-3f9a2"), Ok(play(Some(id), None)));
+        assert_eq!(parse_args("play H-3f9a2"), Ok(play(Some(id), None)));
+        assert_eq!(
+            parse_args("--seed 0x3f9a2 --difficulty=hard"),
+            Ok(play(Some(id), Some(Difficulty::Hard)))
+        );
+
+        let medium = PuzzleId {
+            difficulty: Difficulty::Medium,
+            seed: 12,
+        };
+        assert_eq!(parse_args("play -s 12"), Ok(play(Some(medium), None)));
+        assert_eq!(
+            parse_args("play -f puzzle.txt"),
+            Ok(Command::Play {
+                id: None,
+                difficulty: None,
+                file: Some("puzzle.txt".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_files_or_standard_input() {
+        assert_eq!(
+            parse_args("solve"),
+            Ok(Command::Solve {
+                file: None,
+                solver: Backend::Backtracking,
+            })
+        );
+        assert_eq!(
+            parse_args("validate - --solver dlx"),
+            Ok(Command::Validate {
+                file: None,
+                solver: Backend::DancingLinks,
+            })
+        );
+        assert_eq!(
+            parse_args("rate puzzles.txt"),
+            Ok(Command::Rate {
+                file: Some("puzzles.txt".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_args("convert --to grid puzzles.txt"),
+            Ok(Command::Convert {
+                file: Some("puzzles.txt".to_string()),
+                to: Format::Grid,
+            })
+        );
+    }
+
+    #[test]
+    fn generate_options() {
+        assert_eq!(
+            parse_args("generate"),
+            Ok(Command::Generate(Generate {
+                difficulty: None,
+                rating: None,
+                count: 1,
+                seed: None,
+                symmetry: Symmetry::None,
+                format: Format::Csv,
+                output: None,
+                dedup: true,
+            }))
+        );
+        assert_eq!(
+            parse_args(
+                "generate -d f -r 2.5-4 --count=10 -s 7 -y mirror -F line -o out.txt --no-dedup"
+            ),
+            Ok(Command::Generate(Generate {
+                difficulty: Some(Difficulty::Fiendish),
+                rating: Some((2.5, 4.0)),
+                count: 10,
+                seed: Some(7),
+                symmetry: Symmetry::Mirror,
+                format: Format::Line,
+                output: Some("out.txt".to_string()),
+                dedup: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse_args("help"), Ok(Command::Help));
+        assert_eq!(parse_args("-h"), Ok(Command::Help));
+        assert_eq!(parse_args("generate --help"), Ok(Command::Help));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for args in [
+            "frobnicate",
+            "play --bogus 1",
+            "play -d",
+            "play -d impossible",
+            "play -s 0xzz",
+            "play H-3f9a2 extra",
+            "solve a.txt b.txt",
+            "solve --solver magic",
+            "generate -n lots",
+            "generate -r 4-2",
+            "generate -y spiral",
+            "solve --no-dedup",
+            "convert puzzles.txt",
+            "convert --to xml",
+        ]
+        .iter()
+        {
+            assert!(parse_args(args).is_err(), "{}", args);
+        }
+    }
+}
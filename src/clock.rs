@@ -0,0 +1,57 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A stopwatch for how long a puzzle has been played
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// Time accumulated before the current run
+    elapsed: Duration,
+    /// When the clock was last started, None while it's stopped
+    started: Option<Instant>,
+}
+
+impl Clock {
+    /// A running clock that has already counted `elapsed`
+    pub fn start(elapsed: Duration) -> Clock {
+        Clock {
+            elapsed,
+            started: Some(Instant::now()),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.started {
+            Some(started) => self.elapsed + started.elapsed(),
+            None => self.elapsed,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn stop(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started = None;
+    }
+
+    pub fn resume(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+}
+
+/// A duration shown like "4:05", or "1:04:05" once it's over an hour
+pub struct Elapsed(pub Duration);
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs();
+        if secs >= 3600 {
+            write!(f, "{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            write!(f, "{}:{:02}", secs / 60, secs % 60)
+        }
+    }
+}
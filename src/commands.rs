@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+
+use cli::{Command, Generate};
+use format::{self, Puzzles};
+use grid::generator::{Difficulty, Generator, PuzzleId, Symmetry, DIFFICULTIES, SEED_LIMIT};
+use grid::rating;
+use grid::solver::{Backend, Solver};
+use grid::{self, Grid};
+
+/// How many puzzles `generate` tries for each one asked for before giving up
+const ATTEMPTS_PER_PUZZLE: usize = 100;
+
+/// Run one of the commands that don't need the terminal,
+/// returning the exit code
+pub fn run(command: Command) -> i32 {
+    let result = match command {
+        Command::Solve { file, solver } => solve(file, solver),
+        Command::Generate(options) => generate(options),
+        Command::Rate { file } => read_puzzles(file).map(|(_, puzzles)| {
+            for values in puzzles.iter() {
+                println!("{}", rating::rate_values(values));
+            }
+            true
+        }),
+        Command::Validate { file, solver } => validate(file, solver),
+        Command::Convert { file, to } => read_puzzles(file).map(|(_, puzzles)| {
+            print!("{}", format::write_puzzles(&puzzles, to));
+            true
+        }),
+        Command::Play { .. } | Command::Help => unreachable!(),
+    };
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("{err}");
+            2
+        }
+    }
+}
+
+/// Read the file, or standard input if it's None
+pub fn read_input(file: &Option<String>) -> Result<String, String> {
+    match *file {
+        Some(ref path) => fs::read_to_string(path).map_err(|err| format!("{path}: {err}")),
+        None => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("Reading standard input: {err}"))?;
+            Ok(text)
+        }
+    }
+}
+
+/// Read the puzzles and the format they were written in
+pub fn read_puzzles(file: Option<String>) -> Result<Puzzles, String> {
+    format::parse_puzzles(&read_input(&file)?)
+}
+
+/// Print the solution of every puzzle in the format it was given in,
+/// or the puzzle itself if it has no solution or more than one, so the
+/// output lines up with the input. Returns false if any wasn't solved
+fn solve(file: Option<String>, solver: Backend) -> Result<bool, String> {
+    let (format, puzzles) = read_puzzles(file)?;
+    let mut all_solved = true;
+    let mut solutions = vec![];
+    for (n, values) in puzzles.iter().enumerate() {
+        let found = solver.solutions(values, 2);
+        match found.len() {
+            0 => eprintln!("Puzzle {} has no solution", n + 1),
+            1 => {}
+            _ => eprintln!("Puzzle {} has more than one solution", n + 1),
+        }
+        solutions.push(if found.len() == 1 { found[0] } else { *values });
+        all_solved &= found.len() == 1;
+    }
+    print!("{}", format::write_puzzles(&solutions, format));
+    Ok(all_solved)
+}
+
+/// Print `count` puzzles matching the difficulty, rating range and symmetry
+/// asked for, to `output` if it's given. Returns false if not enough
+/// puzzles turned up
+fn generate(options: Generate) -> Result<bool, String> {
+    let attempts = options.count.saturating_mul(ATTEMPTS_PER_PUZZLE);
+    let puzzles = find_puzzles(&options, attempts);
+
+    let text = format::write_puzzles(&puzzles, options.format);
+    match options.output {
+        Some(ref path) => fs::write(path, text).map_err(|err| format!("{path}: {err}"))?,
+        None => print!("{text}"),
+    }
+
+    if puzzles.len() < options.count {
+        eprintln!(
+            "Only found {} of {} puzzles after {} attempts",
+            puzzles.len(),
+            options.count,
+            attempts
+        );
+    }
+    Ok(puzzles.len() == options.count)
+}
+
+/// Up to `count` puzzles as asked for, trying at most `attempts` seeds.
+/// Seeds count up from `seed` if it's given, so the same options always
+/// give the same puzzles
+fn find_puzzles(options: &Generate, attempts: usize) -> Vec<[[u8; 9]; 9]> {
+    let mut puzzles = vec![];
+    let mut seen = HashSet::new();
+
+    for k in 0..attempts {
+        if puzzles.len() == options.count {
+            break;
+        }
+
+        let seed = match options.seed {
+            Some(seed) => seed.wrapping_add(k as u32),
+            None => fastrand::u32(..SEED_LIMIT),
+        };
+        // A rating range on its own can be met by any difficulty
+        let difficulty = match (options.difficulty, options.rating) {
+            (Some(difficulty), _) => difficulty,
+            (None, Some(_)) => DIFFICULTIES[seed as usize % DIFFICULTIES.len()],
+            (None, None) => Difficulty::Medium,
+        };
+
+        // Puzzles from ids may be disguised seed puzzles,
+        // which don't keep to any symmetry
+        let id = PuzzleId { difficulty, seed };
+        let grid = if options.symmetry == Symmetry::None {
+            Generator::from_id(id)
+        } else {
+            match Generator::from_scratch(difficulty, options.symmetry, &mut id.rng()) {
+                Some(grid) => grid,
+                None => continue,
+            }
+        };
+
+        if wanted(options, &grid, &mut seen) {
+            puzzles.push(grid.clues());
+        }
+    }
+
+    puzzles
+}
+
+/// Whether the puzzle's rating is in the range asked for and, unless
+/// `dedup` is off, it isn't the same as one in `seen` in disguise
+fn wanted(options: &Generate, grid: &Grid, seen: &mut HashSet<u64>) -> bool {
+    if let Some((min, max)) = options.rating {
+        let score = rating::rate(grid).score;
+        if score < min || score > max {
+            return false;
+        }
+    }
+    // Disguises of the same puzzle share a fingerprint
+    !options.dedup || seen.insert(grid.fingerprint())
+}
+
+/// What's wrong with the puzzle, or None if its clues are consistent
+/// and it has exactly one solution
+pub fn problem(values: &[[u8; 9]; 9], solver: Backend) -> Option<&'static str> {
+    if !grid::conflicting_squares(values).is_empty() {
+        Some("clues conflict with each other")
+    } else {
+        match solver.count_solutions(values, 2) {
+            0 => Some("no solution"),
+            1 => None,
+            _ => Some("more than one solution"),
+        }
+    }
+}
+
+/// Check every puzzle has consistent clues and exactly one solution,
+/// printing what's wrong with the ones that don't
+fn validate(file: Option<String>, solver: Backend) -> Result<bool, String> {
+    let mut all_valid = true;
+    for (n, values) in read_puzzles(file)?.1.iter().enumerate() {
+        match problem(values, solver) {
+            Some(problem) => {
+                println!("Puzzle {}: {}", n + 1, problem);
+                all_valid = false;
+            }
+            None => println!("Puzzle {}: valid", n + 1),
+        }
+    }
+    Ok(all_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastrand::Rng;
+    use format::Format;
+
+    fn options() -> Generate {
+        Generate {
+            difficulty: Some(Difficulty::Easy),
+            rating: None,
+            count: 3,
+            seed: Some(1),
+            symmetry: Symmetry::None,
+            format: Format::Line,
+            output: None,
+            dedup: true,
+        }
+    }
+
+    #[test]
+    fn disguised_repeats_are_dropped_unless_asked_not_to() {
+        let puzzle = Generator::from_id(PuzzleId {
+            difficulty: Difficulty::Easy,
+            seed: 1,
+        });
+        let mut disguised = puzzle.clone();
+        Generator::disguise(&mut disguised, &mut Rng::with_seed(7));
+
+        let mut seen = HashSet::new();
+        assert!(wanted(&options(), &puzzle, &mut seen));
+        assert!(!wanted(&options(), &disguised, &mut seen));
+
+        let keep_all = Generate {
+            dedup: false,
+            ..options()
+        };
+        let mut seen = HashSet::new();
+        assert!(wanted(&keep_all, &puzzle, &mut seen));
+        assert!(wanted(&keep_all, &disguised, &mut seen));
+    }
+
+    #[test]
+    fn puzzles_outside_the_rating_range_are_dropped() {
+        let puzzle = Generator::from_id(PuzzleId {
+            difficulty: Difficulty::Easy,
+            seed: 1,
+        });
+        let score = rating::rate(&puzzle).score;
+
+        let rated = |min, max| Generate {
+            rating: Some((min, max)),
+            ..options()
+        };
+        assert!(wanted(&rated(score, score), &puzzle, &mut HashSet::new()));
+        assert!(!wanted(
+            &rated(0.0, score - 0.01),
+            &puzzle,
+            &mut HashSet::new()
+        ));
+        assert!(!wanted(
+            &rated(score + 0.01, 10.0),
+            &puzzle,
+            &mut HashSet::new()
+        ));
+    }
+
+    #[test]
+    fn found_puzzles_are_rated_in_range_and_all_different() {
+        let options = Generate {
+            difficulty: None,
+            rating: Some((1.5, 2.5)),
+            ..options()
+        };
+        let puzzles = find_puzzles(&options, 100);
+
+        assert_eq!(puzzles.len(), options.count);
+        for values in puzzles.iter() {
+            let score = rating::rate_values(values).score;
+            assert!((1.5..=2.5).contains(&score), "{}", score);
+        }
+        let fingerprints: HashSet<u64> = puzzles
+            .iter()
+            .map(|&values| Grid::new(values).fingerprint())
+            .collect();
+        assert_eq!(fingerprints.len(), puzzles.len());
+        assert_eq!(find_puzzles(&options, 100), puzzles);
+    }
+
+    #[test]
+    fn symmetric_puzzles_keep_to_their_symmetry() {
+        for &symmetry in [Symmetry::Rotational, Symmetry::Mirror].iter() {
+            let options = Generate {
+                symmetry,
+                ..options()
+            };
+            let puzzles = find_puzzles(&options, 100);
+
+            assert_eq!(puzzles.len(), options.count);
+            for values in puzzles.iter() {
+                assert!(symmetry.matches(values), "{:?}", symmetry);
+            }
+
+            let mut lopsided = [[0; 9]; 9];
+            lopsided[0][0] = 1;
+            assert!(!symmetry.matches(&lopsided), "{:?}", symmetry);
+        }
+    }
+}
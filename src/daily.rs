@@ -0,0 +1,166 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use grid::generator::{Difficulty, PuzzleId, SEED_LIMIT};
+use storage;
+
+const COMPLETED_FILE: &str = "daily";
+
+/// A day in UTC, counted from the unix epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    days: i64,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Date {
+            days: (secs / 86400) as i64,
+        }
+    }
+
+    /// The daily puzzle of the given difficulty, the same for everyone
+    pub fn puzzle_id(self, difficulty: Difficulty) -> PuzzleId {
+        // splitmix64 finalizer, so neighbouring days get unrelated seeds
+        let mut x = (self.days as u64) << 8 | difficulty as u64;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+
+        PuzzleId {
+            difficulty,
+            seed: (x % u64::from(SEED_LIMIT)) as u32,
+        }
+    }
+
+    /// Year, month and day of the date
+    pub fn ymd(self) -> (i64, u32, u32) {
+        // Howard Hinnant's civil_from_days
+        let z = self.days + 719_468;
+        let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Parse a date written like "2026-10-17"
+    fn from_str(s: &str) -> Result<Date, String> {
+        let invalid = || format!("Invalid date: {s}");
+
+        let parts: Vec<&str> = s.split('-').collect();
+        let &[year, month, day] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let year: i64 = year.parse().map_err(|_| invalid())?;
+        let month: i64 = month.parse().map_err(|_| invalid())?;
+        let day: i64 = day.parse().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(invalid());
+        }
+
+        // Howard Hinnant's days_from_civil
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = if year >= 0 { year } else { year - 399 } / 400;
+        let yoe = year - era * 400;
+        let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        Ok(Date {
+            days: era * 146_097 + doe - 719_468,
+        })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Difficulties whose daily puzzle was completed on the given date
+pub fn completed(date: Date) -> Vec<Difficulty> {
+    let Ok(path) = storage::data_file(COMPLETED_FILE) else {
+        return vec![];
+    };
+    let Ok(contents) = fs::read_to_string(path) else {
+        return vec![];
+    };
+
+    let date = date.to_string();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some(date.as_str()) {
+                return None;
+            }
+            parts
+                .next()?
+                .chars()
+                .next()
+                .and_then(Difficulty::from_letter)
+        })
+        .collect()
+}
+
+pub fn record_completed(date: Date, difficulty: Difficulty) -> io::Result<()> {
+    if completed(date).contains(&difficulty) {
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(storage::data_file(COMPLETED_FILE)?)?;
+    writeln!(file, "{} {}", date, difficulty.letter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        let dates = [
+            ("1970-01-01", 0),
+            ("2000-02-29", 11_016),
+            ("1969-12-31", -1),
+            ("2100-03-01", 47_541),
+        ];
+        for &(text, days) in dates.iter() {
+            let date: Date = text.parse().unwrap();
+            assert_eq!(date, Date { days }, "{}", text);
+            assert_eq!(date.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn every_day_round_trips() {
+        for days in -1000..100_000 {
+            let date = Date { days };
+            assert_eq!(date.to_string().parse::<Date>(), Ok(date));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_dates() {
+        for text in ["", "2026-10", "2026-13-01", "2026-10-32", "26/10/2026"].iter() {
+            assert!(text.parse::<Date>().is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Ways of writing puzzles as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One puzzle per line, 81 characters read row by row,
+    /// with `.`, `0` or `_` for empty squares
+    Line,
+    /// Nine lines of comma separated digits, with nothing or 0
+    /// for empty squares, like the seed puzzles
+    Csv,
+    /// A board for people to read, with `.` for empty squares
+    /// and lines between the blocks
+    Grid,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "line" => Ok(Format::Line),
+            "csv" => Ok(Format::Csv),
+            "grid" => Ok(Format::Grid),
+            _ => Err(format!("Unknown format: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Format::Line => write!(f, "line"),
+            Format::Csv => write!(f, "csv"),
+            Format::Grid => write!(f, "grid"),
+        }
+    }
+}
+
+/// Puzzles read from text, along with the format they were written in
+pub type Puzzles = (Format, Vec<[[u8; 9]; 9]>);
+
+/// Read every puzzle in the text, working out which format it's in
+/// from the first line. Blank lines and lines starting with `#` are skipped,
+/// but text with no puzzles at all is an error
+pub fn parse_puzzles(text: &str) -> Result<Puzzles, String> {
+    let (format, puzzles) = parse_lines(text)?;
+    if puzzles.is_empty() {
+        return Err("No puzzles found".to_string());
+    }
+    Ok((format, puzzles))
+}
+
+fn parse_lines(text: &str) -> Result<Puzzles, String> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|&(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let Some(&(_, first)) = lines.first() else {
+        return Ok((Format::Line, vec![]));
+    };
+
+    if first.contains(',') {
+        parse_blocks(&lines, parse_csv_row).map(|puzzles| (Format::Csv, puzzles))
+    } else if first.chars().filter(|ch| !ch.is_whitespace()).count() >= 81 {
+        let puzzles = lines
+            .iter()
+            .map(|&(n, line)| parse_line(line).map_err(|err| format!("Line {n}: {err}")))
+            .collect::<Result<_, _>>()?;
+        Ok((Format::Line, puzzles))
+    } else {
+        // Lines made only of separators, like "------+-------+------", don't count
+        let rows: Vec<(usize, &str)> = lines
+            .into_iter()
+            .filter(|&(_, line)| !line.chars().all(is_separator))
+            .collect();
+        parse_blocks(&rows, parse_grid_row).map(|puzzles| (Format::Grid, puzzles))
+    }
+}
+
+/// Read puzzles from every 9 rows, each parsed by `parse_row`
+fn parse_blocks<F>(rows: &[(usize, &str)], parse_row: F) -> Result<Vec<[[u8; 9]; 9]>, String>
+where
+    F: Fn(&str) -> Result<[u8; 9], String>,
+{
+    if !rows.len().is_multiple_of(9) {
+        return Err("Puzzles must be 9 rows long".to_string());
+    }
+
+    let mut puzzles = vec![];
+    for block in rows.chunks(9) {
+        let mut values = [[0; 9]; 9];
+        for (row, &(n, line)) in values.iter_mut().zip(block) {
+            *row = parse_row(line).map_err(|err| format!("Line {n}: {err}"))?;
+        }
+        puzzles.push(values);
+    }
+    Ok(puzzles)
+}
+
+fn is_separator(ch: char) -> bool {
+    ch == '-' || ch == '+' || ch == '|' || ch.is_whitespace()
+}
+
+fn square_value(ch: char) -> Option<u8> {
+    match ch {
+        '.' | '_' => Some(0),
+        '0'..='9' => Some(ch as u8 - b'0'),
+        _ => None,
+    }
+}
+
+/// Read a single puzzle written on one line
+pub fn parse_line(line: &str) -> Result<[[u8; 9]; 9], String> {
+    let squares: Vec<char> = line.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if squares.len() != 81 {
+        return Err(format!("expected 81 squares, found {}", squares.len()));
+    }
+
+    let mut values = [[0; 9]; 9];
+    for (idx, &ch) in squares.iter().enumerate() {
+        values[idx / 9][idx % 9] =
+            square_value(ch).ok_or_else(|| format!("invalid square {ch:?}"))?;
+    }
+    Ok(values)
+}
+
+/// Read a single puzzle written as 9 lines of comma separated digits,
+/// like the seed puzzles
+pub fn parse_csv(csv: &str) -> Result<[[u8; 9]; 9], String> {
+    let rows: Vec<(usize, &str)> = csv
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|&(_, line)| !line.is_empty())
+        .collect();
+    if rows.len() != 9 {
+        return Err(format!("expected 9 rows, found {}", rows.len()));
+    }
+    Ok(parse_blocks(&rows, parse_csv_row)?[0])
+}
+
+fn parse_csv_row(line: &str) -> Result<[u8; 9], String> {
+    let digits: Vec<&str> = line.split(',').map(|digit| digit.trim()).collect();
+    if digits.len() != 9 {
+        return Err("expected 9 comma separated digits".to_string());
+    }
+
+    let mut row = [0; 9];
+    for (value, digit) in row.iter_mut().zip(digits) {
+        *value = match digit.parse() {
+            _ if digit.is_empty() => 0,
+            Ok(digit @ 0..=9) => digit,
+            _ => return Err(format!("invalid digit {digit:?}")),
+        };
+    }
+    Ok(row)
+}
+
+/// A row of a grid like "5 3 . | . 7 . | . . .", ignoring everything
+/// but the squares
+fn parse_grid_row(line: &str) -> Result<[u8; 9], String> {
+    let values: Vec<u8> = line.chars().filter_map(square_value).collect();
+    if values.len() != 9 {
+        return Err(format!("expected 9 squares, found {}", values.len()));
+    }
+
+    let mut row = [0; 9];
+    row.copy_from_slice(&values);
+    Ok(row)
+}
+
+/// The puzzle on one line with `.` for empty squares, without a newline
+pub fn write_line(values: &[[u8; 9]; 9]) -> String {
+    values
+        .iter()
+        .flatten()
+        .map(|&value| match value {
+            0 => '.',
+            value => (b'0' + value) as char,
+        })
+        .collect()
+}
+
+/// Write the puzzle in the given format, ending with a newline
+pub fn write_puzzle(values: &[[u8; 9]; 9], format: Format) -> String {
+    let mut text = String::new();
+    match format {
+        Format::Line => {
+            text.push_str(&write_line(values));
+            text.push('\n');
+        }
+        Format::Csv => {
+            for row in values.iter() {
+                let digits: Vec<String> = row
+                    .iter()
+                    .map(|&value| match value {
+                        0 => String::new(),
+                        value => value.to_string(),
+                    })
+                    .collect();
+                text.push_str(&digits.join(","));
+                text.push('\n');
+            }
+        }
+        Format::Grid => {
+            for (i, row) in values.iter().enumerate() {
+                if i > 0 && i % 3 == 0 {
+                    text.push_str("------+-------+------\n");
+                }
+                let blocks: Vec<String> = row
+                    .chunks(3)
+                    .map(|block| {
+                        let digits: Vec<String> = block
+                            .iter()
+                            .map(|&value| match value {
+                                0 => ".".to_string(),
+                                value => value.to_string(),
+                            })
+                            .collect();
+                        digits.join(" ")
+                    })
+                    .collect();
+                text.push_str(&blocks.join(" | "));
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+/// Write several puzzles, with a blank line between grids
+pub fn write_puzzles(puzzles: &[[[u8; 9]; 9]], format: Format) -> String {
+    let written: Vec<String> = puzzles
+        .iter()
+        .map(|values| write_puzzle(values, format))
+        .collect();
+    match format {
+        Format::Line | Format::Csv => written.concat(),
+        Format::Grid => written.join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grid::generator::{read_puzzles, Difficulty};
+
+    fn puzzles() -> Vec<[[u8; 9]; 9]> {
+        read_puzzles(Difficulty::Hard.puzzles())
+            .iter()
+            .take(3)
+            .map(|puzzle| puzzle.clues())
+            .collect()
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let puzzles = puzzles();
+        for &format in [Format::Line, Format::Csv, Format::Grid].iter() {
+            let text = write_puzzles(&puzzles, format);
+            assert_eq!(
+                parse_puzzles(&text),
+                Ok((format, puzzles.clone())),
+                "{}",
+                format
+            );
+            assert_eq!(format.to_string().parse(), Ok(format));
+        }
+    }
+
+    #[test]
+    fn reads_every_way_of_writing_empty_squares() {
+        let values = puzzles()[0];
+        let line = write_puzzle(&values, Format::Line);
+        for &blank in ["0", "_"].iter() {
+            let text = line.replace('.', blank);
+            assert_eq!(parse_puzzles(&text), Ok((Format::Line, vec![values])));
+        }
+
+        let csv = write_puzzle(&values, Format::Csv).replace(",,", ",0,");
+        assert_eq!(parse_puzzles(&csv), Ok((Format::Csv, vec![values])));
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let values = puzzles()[0];
+        let text = format!("# a puzzle\n\n{}\n", write_puzzle(&values, Format::Grid));
+        assert_eq!(parse_puzzles(&text), Ok((Format::Grid, vec![values])));
+    }
+
+    #[test]
+    fn rejects_text_without_puzzles() {
+        for text in [
+            "",
+            " \n\n",
+            "abc\n",
+            "# just a comment\n",
+            "------+-------+------\n",
+        ]
+        .iter()
+        {
+            assert!(parse_puzzles(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_puzzles() {
+        let line = write_puzzle(&puzzles()[0], Format::Line);
+        let grid = write_puzzle(&puzzles()[0], Format::Grid);
+        for text in [
+            line.replacen('.', "x", 1),
+            format!("{}.", line.trim()),
+            "1,2,3\n".repeat(9),
+            grid.lines().skip(1).collect::<Vec<_>>().join("\n"),
+        ]
+        .iter()
+        {
+            assert!(parse_puzzles(text).is_err(), "{}", text);
+        }
+    }
+}
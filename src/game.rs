@@ -1,24 +1,89 @@
+use clock::{Clock, Elapsed};
+use daily::{self, Date};
 use grid::generator::Difficulty;
 use grid::generator::Generator;
+use grid::generator::PuzzleId;
+use grid::generator::DIFFICULTIES;
+use grid::logic::Step;
+use grid::rating;
 use grid::Direction;
-use grid::Grid;
+use grid::{Grid, Theme};
+use grid::{BOARD_HEIGHT, BOARD_WIDTH};
+use layout::{Layout, PANEL_WIDTH};
+use save::{self, SavedGame};
+use stats;
 
 use termion;
 use termion::clear;
+use termion::color;
 use termion::cursor;
-use termion::event::{Event, Key};
-use termion::input::{Events, TermRead};
+use termion::event::{Event, Key, MouseButton, MouseEvent};
+use termion::input::TermRead;
 use termion::style;
 
+use std::collections::HashSet;
 use std::io::{Read, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 
-pub struct Game<R, W: Write> {
+/// How often the board is redrawn to keep the clock up to date
+const TICK: Duration = Duration::from_millis(250);
+
+/// Shifted number keys on a US layout, which select the digit to stamp
+const SHIFTED_DIGITS: &str = "!@#$%^&*(";
+
+/// What the player picked in the start menu
+#[derive(Debug, Clone, Copy)]
+pub enum MenuChoice {
+    Resume,
+    New(Difficulty),
+    Daily(Difficulty),
+}
+
+/// A hint about the next logical step, revealed a level at a time:
+/// first the block to look at, then the technique, then the step itself
+struct Hint {
+    step: Step,
+    level: usize,
+    /// The board the hint was given for
+    values: [[u8; 9]; 9],
+}
+
+pub struct Game<W: Write> {
     grid: Grid,
+    /// None for puzzles that didn't come from the generator
+    id: Option<PuzzleId>,
+    difficulty: Difficulty,
+    /// Set when playing the daily puzzle of that date
+    daily: Option<Date>,
+    /// Whether digit keys toggle notes instead of filling squares
+    notes_mode: bool,
+    /// Digit selected for digit-first input, stamped on each square
+    /// the player picks instead of typing a digit per square
+    stamp: Option<u8>,
+    auto_prune: bool,
+    theme: Theme,
+    hint: Option<Hint>,
+    hints_used: usize,
+    clock: Clock,
+    /// Whole seconds on the clock when the status line was last drawn
+    shown_secs: u64,
+    paused: bool,
+    /// Digits placed or cleared in the current puzzle
+    moves: usize,
+    /// Digits placed that don't match the solution
+    mistakes: usize,
+    /// Square being scrolled through with the mouse wheel and its value
+    /// before, counted as a single move once the player does something else
+    scrolling: Option<((usize, usize), u8)>,
     stdout: W,
-    events: Events<R>,
+    /// Input events, read on a separate thread so the clock
+    /// can keep ticking while waiting for them
+    events: Receiver<Event>,
 }
 
-impl<R, W: Write> Drop for Game<R, W> {
+impl<W: Write> Drop for Game<W> {
     fn drop(&mut self) {
         write!(
             self.stdout,
@@ -32,11 +97,36 @@ impl<R, W: Write> Drop for Game<R, W> {
     }
 }
 
-impl<R: TermRead + Read, W: Write> Game<R, W> {
-    pub fn new(stdin: R, stdout: W) -> Game<R, W> {
+impl<W: Write> Game<W> {
+    pub fn new<R: Read + Send + 'static>(stdin: R, stdout: W) -> Game<W> {
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            for evt in stdin.events() {
+                if sender.send(evt.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let id = PuzzleId::random(Difficulty::VeryEasy);
         Game {
-            grid: Generator::generate("very easy"),
-            events: stdin.events(),
+            grid: Generator::from_id(id),
+            id: Some(id),
+            difficulty: id.difficulty,
+            daily: None,
+            notes_mode: false,
+            stamp: None,
+            auto_prune: false,
+            theme: Theme::load(),
+            hint: None,
+            hints_used: 0,
+            clock: Clock::start(Duration::ZERO),
+            shown_secs: 0,
+            paused: false,
+            moves: 0,
+            mistakes: 0,
+            scrolling: None,
+            events,
             stdout,
         }
     }
@@ -52,31 +142,20 @@ impl<R: TermRead + Read, W: Write> Game<R, W> {
         .unwrap();
     }
 
-    /// Returns None if user decided to quit instead
-    pub fn get_difficulty(&mut self) -> Option<Difficulty> {
-        let difficulties = [
-            Difficulty::VeryEasy,
-            Difficulty::Easy,
-            Difficulty::Medium,
-            Difficulty::Hard,
-            Difficulty::Fiendish,
-        ];
+    /// Let the player pick one of the items.
+    /// Returns None if they decided to go back instead
+    fn menu(&mut self, title: &str, items: &[String]) -> Option<usize> {
         let mut current_index = 0;
 
         loop {
             let (w, h) = termion::terminal_size().unwrap();
-            let top = (h - 6) / 2;
+            let top = (h - items.len() as u16) / 2;
             let left = (w - 20) / 2;
 
             write!(self.stdout, "{}", clear::All).unwrap();
-            write!(
-                self.stdout,
-                "{}Choose a difficulty:",
-                cursor::Goto(left, top - 1)
-            )
-            .unwrap();
+            write!(self.stdout, "{}{}", cursor::Goto(left, top - 1), title).unwrap();
 
-            for (i, diff) in difficulties.iter().enumerate() {
+            for (i, item) in items.iter().enumerate() {
                 if i == current_index {
                     write!(self.stdout, "{}>>", cursor::Goto(left + 2, top + i as u16)).unwrap();
                 }
@@ -84,65 +163,257 @@ impl<R: TermRead + Read, W: Write> Game<R, W> {
                     self.stdout,
                     "{}{}",
                     cursor::Goto(left + 5, top + i as u16),
-                    diff
+                    item
                 )
                 .unwrap();
             }
             self.stdout.flush().unwrap();
 
-            let evt = self.events.next().unwrap().unwrap();
+            let evt = self.events.recv().unwrap();
 
             if let Event::Key(key) = evt {
                 match key {
                     Key::Char('q') | Key::Ctrl('c' | 'd') => return None,
                     Key::Down | Key::Char('j' | 's') => {
-                        current_index += 1;
+                        current_index = (current_index + 1) % items.len();
                     }
                     Key::Up | Key::Char('k' | 'w') => {
                         current_index = if current_index > 0 {
                             current_index - 1
                         } else {
-                            difficulties.len() - 1
+                            items.len() - 1
                         };
                     }
-                    Key::Char('\n') => return Some(difficulties[current_index]),
+                    Key::Char('\n') => return Some(current_index),
                     _ => {}
                 }
             }
+
+            if let Event::Mouse(MouseEvent::Press(MouseButton::Left, _, y)) = evt {
+                if y >= top && ((y - top) as usize) < items.len() {
+                    return Some((y - top) as usize);
+                }
+            }
         }
     }
 
+    /// Returns None if user decided to quit instead
+    pub fn get_difficulty(&mut self) -> Option<MenuChoice> {
+        loop {
+            let mut items = vec![];
+            if save::exists() {
+                items.push("Resume".to_string());
+            }
+            let offset = items.len();
+            items.extend(DIFFICULTIES.iter().map(|d| d.to_string()));
+            items.push("Daily".to_string());
+            items.push("Statistics".to_string());
+
+            let index = self.menu("Choose a difficulty:", &items)?;
+            if index < offset {
+                return Some(MenuChoice::Resume);
+            }
+            if index - offset < DIFFICULTIES.len() {
+                return Some(MenuChoice::New(DIFFICULTIES[index - offset]));
+            }
+            if index == items.len() - 1 {
+                self.show_stats();
+            } else if let Some(diff) = self.get_daily_difficulty() {
+                return Some(MenuChoice::Daily(diff));
+            }
+        }
+    }
+
+    /// Show the statistics for every difficulty until a key is pressed
+    fn show_stats(&mut self) {
+        let mut lines = vec![
+            "Statistics".to_string(),
+            String::new(),
+            format!(
+                "{:<10} {:>8} {:>10} {:>8} {:>8} {:>6} {:>7}",
+                "", "Started", "Completed", "Best", "Average", "Hints", "Streak"
+            ),
+        ];
+        for &diff in DIFFICULTIES.iter() {
+            let stats = stats::load(diff);
+            let time = |time: Option<Duration>| {
+                time.map_or("-".to_string(), |time| Elapsed(time).to_string())
+            };
+            lines.push(format!(
+                "{:<10} {:>8} {:>10} {:>8} {:>8} {:>6} {:>7}",
+                diff.to_string(),
+                stats.started,
+                stats.completed,
+                time(stats.best),
+                time(stats.average()),
+                stats.hints_used,
+                stats.streak
+            ));
+        }
+        lines.push(String::new());
+        lines.push("Press any key to go back".to_string());
+
+        let (w, h) = termion::terminal_size().unwrap();
+        let top = h.saturating_sub(lines.len() as u16) / 2 + 1;
+        let left = w.saturating_sub(lines[2].len() as u16) / 2 + 1;
+
+        write!(self.stdout, "{}", clear::All).unwrap();
+        for (i, line) in lines.iter().enumerate() {
+            write!(
+                self.stdout,
+                "{}{}",
+                cursor::Goto(left, top + i as u16),
+                line
+            )
+            .unwrap();
+        }
+        self.stdout.flush().unwrap();
+
+        while let Ok(evt) = self.events.recv() {
+            if let Event::Key(_) = evt {
+                break;
+            }
+        }
+    }
+
+    /// Returns None if user decided to go back instead
+    fn get_daily_difficulty(&mut self) -> Option<Difficulty> {
+        let today = Date::today();
+        let completed = daily::completed(today);
+        let items: Vec<String> = DIFFICULTIES
+            .iter()
+            .map(|diff| {
+                if completed.contains(diff) {
+                    format!("{diff} ✓")
+                } else {
+                    diff.to_string()
+                }
+            })
+            .collect();
+
+        let index = self.menu(&format!("Daily puzzles for {today}:"), &items)?;
+        Some(DIFFICULTIES[index])
+    }
+
     pub fn run(&mut self) {
         self.init();
 
-        let Some(diff) = self.get_difficulty() else {
+        let Some(choice) = self.get_difficulty() else {
             return;
         };
 
-        self.grid = Generator::generate(diff);
+        let (id, daily) = match choice {
+            MenuChoice::Resume => {
+                match save::load() {
+                    Some(saved) => self.start(saved),
+                    None => {
+                        // Unreadable, so there's nothing to resume
+                        let _ = save::remove();
+                        self.run();
+                    }
+                }
+                return;
+            }
+            MenuChoice::New(diff) => (PuzzleId::random(diff), None),
+            MenuChoice::Daily(diff) => {
+                let today = Date::today();
+                (today.puzzle_id(diff), Some(today))
+            }
+        };
+        self.daily = daily;
+        self.play(id);
+    }
+
+    /// Play the puzzle with the given id
+    pub fn play(&mut self, id: PuzzleId) {
+        let _ = stats::record_started(id.difficulty);
+        self.start(SavedGame {
+            id: Some(id),
+            difficulty: id.difficulty,
+            daily: self.daily,
+            elapsed: Duration::ZERO,
+            moves: 0,
+            mistakes: 0,
+            hints_used: 0,
+            grid: Generator::from_id(id),
+        });
+    }
+
+    /// Play a puzzle from elsewhere, given by the grid's initial squares
+    pub fn play_grid(&mut self, grid: Grid) {
+        let difficulty = rating::rate(&grid).difficulty();
+        let _ = stats::record_started(difficulty);
+        self.start(SavedGame {
+            id: None,
+            difficulty,
+            daily: None,
+            elapsed: Duration::ZERO,
+            moves: 0,
+            mistakes: 0,
+            hints_used: 0,
+            grid,
+        });
+    }
+
+    /// Play from the given position until the player quits,
+    /// saving after every move so the game can be resumed later
+    fn start(&mut self, game: SavedGame) {
+        self.init();
+
+        self.id = game.id;
+        self.difficulty = game.difficulty;
+        self.daily = game.daily;
+        self.grid = game.grid;
+        self.grid.set_auto_prune(self.auto_prune);
+        self.grid.set_theme(self.theme);
+        self.grid.select_digit(self.stamp);
+        self.hint = None;
+        self.hints_used = game.hints_used;
+        self.clock = Clock::start(game.elapsed);
+        self.paused = false;
+        self.moves = game.moves;
+        self.mistakes = game.mistakes;
+        self.scrolling = None;
 
-        let (w, h) = termion::terminal_size().unwrap();
-        let top = (h - 19) / 2;
-        let left = (w - 37) / 2;
         let mut message = String::new();
+        let mut solved = false;
 
-        writeln!(
-            self.stdout,
-            "{}{}{}",
-            clear::All,
-            cursor::Goto(left, top),
-            self.grid
-        )
-        .unwrap();
+        self.draw(&message);
         loop {
-            let evt = self.events.next().unwrap().unwrap();
+            let evt = match self.events.recv_timeout(TICK) {
+                Ok(evt) => evt,
+                Err(RecvTimeoutError::Timeout) => {
+                    // Only the clock changes while waiting, so leave
+                    // the board alone to avoid flicker
+                    if self.clock.is_running() && self.clock.elapsed().as_secs() != self.shown_secs
+                    {
+                        let layout = self.layout();
+                        self.draw_status(&layout);
+                        self.stdout.flush().unwrap();
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if !is_scroll(&evt) {
+                self.settle_scroll();
+            }
 
-            let (w, h) = termion::terminal_size().unwrap();
-            let top = (h - 19) / 2;
-            let left = (w - 37) / 2;
+            if self.paused {
+                match evt {
+                    Event::Key(Key::Char('p')) => {
+                        self.paused = false;
+                        self.clock.resume();
+                    }
+                    Event::Key(Key::Char('q') | Key::Ctrl('c' | 'd')) => break,
+                    _ => {}
+                }
+                self.draw(&message);
+                continue;
+            }
 
-            if let Event::Key(key) = evt {
-                match key {
+            match evt {
+                Event::Key(key) => match key {
                     Key::Right | Key::Char('d' | 'l') => {
                         self.grid.move_cursor(Direction::Right);
                     }
@@ -155,39 +426,497 @@ impl<R: TermRead + Read, W: Write> Game<R, W> {
                     Key::Down | Key::Char('s' | 'j') => {
                         self.grid.move_cursor(Direction::Down);
                     }
-                    Key::Char(' ') | Key::Backspace => self.grid.update_current(0),
+                    Key::Char(' ') | Key::Backspace if self.notes_mode => self.grid.toggle_note(0),
+                    Key::Char(' ') | Key::Backspace => self.place(0),
                     Key::Ctrl('r') => self.grid.redo(),
+                    Key::Char('\n') if self.stamp.is_some() => self.stamp_current(),
+                    Key::Esc => self.select_stamp(None),
                     Key::Char(ch) => match ch {
-                        'q' => return,
+                        'q' => break,
+                        'p' if !solved => {
+                            self.paused = true;
+                            self.clock.stop();
+                        }
                         'u' => self.grid.undo(),
                         'r' => self.grid.remove_filled(),
+                        'm' => self.notes_mode = !self.notes_mode,
+                        'c' => self.grid.fill_notes(),
+                        ch if SHIFTED_DIGITS.contains(ch) => {
+                            let d = SHIFTED_DIGITS.find(ch).unwrap() as u8 + 1;
+                            if self.stamp == Some(d) {
+                                self.select_stamp(None);
+                            } else {
+                                self.select_stamp(Some(d));
+                            }
+                        }
+                        '?' if !solved => message = self.show_hint(),
+                        'x' if !solved => {
+                            message = match self.grid.check().len() {
+                                _ if self.grid.solution().is_none() => {
+                                    "This puzzle has no solution to check against".to_string()
+                                }
+                                0 => "Everything filled in so far is correct".to_string(),
+                                1 => "1 square is wrong".to_string(),
+                                n => format!("{n} squares are wrong"),
+                            };
+                        }
+                        'v' if !solved => {
+                            message = if self.grid.reveal_current() {
+                                String::new()
+                            } else {
+                                "Nothing to reveal here".to_string()
+                            };
+                        }
+                        'V' if !solved => {
+                            if self.grid.reveal_solution() {
+                                solved = true;
+                                self.clock.stop();
+                                self.hint = None;
+                                self.grid.clear_highlights();
+                                message = "Solution revealed, this one doesn't count. \
+                                           Press n to start a new one or q to quit"
+                                    .to_string();
+                            } else {
+                                message = "This puzzle has no solution to reveal".to_string();
+                            }
+                        }
+                        'S' => {
+                            self.theme.same_digit.enabled = !self.theme.same_digit.enabled;
+                            self.grid.set_theme(self.theme);
+                        }
+                        'P' => {
+                            self.theme.peers.enabled = !self.theme.peers.enabled;
+                            self.grid.set_theme(self.theme);
+                        }
+                        'N' => {
+                            self.theme.candidates.enabled = !self.theme.candidates.enabled;
+                            self.grid.set_theme(self.theme);
+                        }
+                        'C' => {
+                            self.auto_prune = !self.auto_prune;
+                            self.grid.set_auto_prune(self.auto_prune);
+                        }
                         'n' => {
+                            self.autosave(solved);
                             self.run();
                             return;
                         }
-                        ch if ch.is_ascii_digit() => {
-                            self.grid.update_current(ch as usize - '0' as usize);
+                        ch if ch.is_ascii_digit() && self.notes_mode => {
+                            self.grid.toggle_note(ch as usize - '0' as usize);
                         }
+                        ch if ch.is_ascii_digit() => self.place(ch as usize - '0' as usize),
                         _ => {}
                     },
                     Key::Ctrl('c' | 'd') => break,
                     _ => {}
-                };
-                if self.grid.is_solved() {
-                    self.grid.freeze();
-                    message = "You finished the puzzle! Press n to start a new one or q to quit"
-                        .to_string();
+                },
+                Event::Mouse(mouse) if !solved => self.click(mouse),
+                _ => {}
+            }
+
+            let stale = match self.hint {
+                Some(ref hint) => hint.values != self.grid.values(),
+                None => false,
+            };
+            if stale {
+                self.hint = None;
+                self.grid.clear_highlights();
+                message.clear();
+            }
+
+            if !solved && self.grid.is_solved() {
+                self.settle_scroll();
+                solved = true;
+                self.clock.stop();
+                self.grid.freeze();
+                message = format!(
+                    "You finished the puzzle in {}! Press n to start a new one or q to quit",
+                    Elapsed(self.clock.elapsed())
+                );
+                if let Some(date) = self.daily {
+                    let _ = daily::record_completed(date, self.difficulty);
                 }
+                let _ =
+                    stats::record_completed(self.difficulty, self.clock.elapsed(), self.hints_used);
+            }
+            self.autosave(solved);
+            self.draw(&message);
+        }
+        self.settle_scroll();
+        self.autosave(solved);
+    }
+
+    /// Save the game in progress, or forget it once it's over
+    fn autosave(&self, finished: bool) {
+        let _ = if finished {
+            save::remove()
+        } else {
+            save::store(&SavedGame {
+                id: self.id,
+                difficulty: self.difficulty,
+                daily: self.daily,
+                elapsed: self.clock.elapsed(),
+                moves: self.moves,
+                mistakes: self.mistakes,
+                hints_used: self.hints_used,
+                grid: self.grid.clone(),
+            })
+        };
+    }
+
+    /// Select the clicked square, or cycle its value with the scroll wheel
+    fn click(&mut self, mouse: MouseEvent) {
+        let (x, y) = match mouse {
+            MouseEvent::Press(_, x, y) => (x, y),
+            _ => return,
+        };
+        let layout = self.layout();
+        if let MouseEvent::Press(MouseButton::Left, _, _) = mouse {
+            if let Some(d) = layout.panel_digit_at(x, y) {
+                self.select_stamp(if self.stamp == Some(d) { None } else { Some(d) });
+                return;
             }
+        }
+        let Some((i, j)) = layout.square_at(x, y) else {
+            return;
+        };
+
+        if (i, j) != self.grid.current() {
+            self.grid.move_cursor_to(i, j);
+        }
+        match mouse {
+            MouseEvent::Press(MouseButton::Left, _, _) if self.stamp.is_some() => {
+                self.stamp_current();
+            }
+            MouseEvent::Press(MouseButton::WheelUp, _, _) => self.scroll(1),
+            MouseEvent::Press(MouseButton::WheelDown, _, _) => self.scroll(9),
+            _ => {}
+        }
+    }
+
+    /// Move the current square's value `step` along from 0 to 9 and round
+    /// again, leaving the counting to `settle_scroll`. Does nothing in
+    /// notes mode
+    fn scroll(&mut self, step: usize) {
+        let (i, j) = self.grid.current();
+        if self.notes_mode || self.grid[i][j].is_initial() {
+            return;
+        }
+
+        let value = self.grid[i][j].value();
+        if self.scrolling.map(|(cell, _)| cell) != Some((i, j)) {
+            self.settle_scroll();
+            self.scrolling = Some(((i, j), value));
+        }
+        self.grid.update_current((value as usize + step) % 10);
+    }
+
+    /// Count the square scrolled through as one move if it ended up
+    /// with a different value
+    fn settle_scroll(&mut self) {
+        let Some(((i, j), before)) = self.scrolling.take() else {
+            return;
+        };
+        let value = self.grid[i][j].value();
+        if value != before {
+            self.count_move(i, j, value as usize);
+        }
+    }
+
+    /// Where everything goes at the current terminal size
+    fn layout(&self) -> Layout {
+        let (w, h) = termion::terminal_size().unwrap();
+        Layout::new(w, h)
+    }
+
+    fn select_stamp(&mut self, stamp: Option<u8>) {
+        self.stamp = stamp;
+        self.grid.select_digit(stamp);
+    }
+
+    /// Put the selected digit in the current square, or in its notes
+    /// in notes mode. Stamping a square that already has it clears it
+    fn stamp_current(&mut self) {
+        let Some(d) = self.stamp else {
+            return;
+        };
+        let (i, j) = self.grid.current();
+        if self.notes_mode {
+            self.grid.toggle_note(d as usize);
+        } else if self.grid[i][j].value() == d {
+            self.place(0);
+        } else {
+            self.place(d as usize);
+        }
+    }
+
+    /// Fill the current square with `d`, counting the move
+    /// and whether it was a mistake
+    fn place(&mut self, d: usize) {
+        let (i, j) = self.grid.current();
+        if self.grid[i][j].is_initial() || self.grid[i][j].value() as usize == d {
+            return;
+        }
+
+        self.grid.update_current(d);
+        self.count_move(i, j, d);
+    }
+
+    /// Count `d` being put in square (i, j), and whether it was a mistake
+    fn count_move(&mut self, i: usize, j: usize, d: usize) {
+        self.moves += 1;
+        if let Some(solution) = self.grid.solution() {
+            if d != 0 && solution[i][j] as usize != d {
+                self.mistakes += 1;
+            }
+        }
+    }
+
+    /// Reveal a little more about the next logical step each time
+    /// it's called while the board stays the same
+    fn show_hint(&mut self) -> String {
+        if !self.grid.find_invalid_squares().is_empty() {
+            return "Fix the conflicting squares before asking for a hint".to_string();
+        }
+
+        let values = self.grid.values();
+        let hint = match self.hint.take() {
+            Some(hint) if hint.values == values => Hint {
+                level: (hint.level + 1).min(3),
+                ..hint
+            },
+            _ => match self.grid.next_step() {
+                Some(step) => Hint {
+                    step,
+                    level: 0,
+                    values,
+                },
+                None => return "No hint available for this position".to_string(),
+            },
+        };
+
+        let targets: Vec<(usize, usize)> = hint
+            .step
+            .placements
+            .iter()
+            .chain(hint.step.eliminations.iter())
+            .map(|&(i, j, _)| (i, j))
+            .collect();
+
+        let level = hint.level.max(1);
+        if level != hint.level {
+            self.hints_used += 1;
+        }
+
+        let message = if level < 3 {
+            let (i, j) = targets[0];
+            let mut block = HashSet::new();
+            for i2 in (i / 3) * 3..(i / 3) * 3 + 3 {
+                for j2 in (j / 3) * 3..(j / 3) * 3 + 3 {
+                    block.insert((i2, j2));
+                }
+            }
+            self.grid.highlight(block);
+
+            if level == 1 {
+                "Hint: there's progress to be made in the highlighted block".to_string()
+            } else {
+                format!(
+                    "Hint: look for a {} in the highlighted block",
+                    hint.step.technique
+                )
+            }
+        } else {
+            let cells = targets
+                .iter()
+                .chain(hint.step.reason_cells.iter())
+                .cloned()
+                .collect();
+            self.grid.highlight(cells);
+
+            format!("Hint: {}", hint.step)
+        };
+
+        self.hint = Some(Hint { level, ..hint });
+        message
+    }
+
+    /// Redraw the board, centered in the terminal, with the status and message lines below it
+    fn draw(&mut self, message: &str) {
+        let layout = self.layout();
+        if self.paused {
+            let text = "Paused, press p to resume";
+            writeln!(
+                self.stdout,
+                "{}{}{}",
+                clear::All,
+                cursor::Goto(
+                    layout.board.x + (BOARD_WIDTH - text.len() as u16) / 2,
+                    layout.board.y + BOARD_HEIGHT / 2
+                ),
+                text
+            )
+            .unwrap();
+        } else {
             writeln!(
                 self.stdout,
                 "{}{}{}",
                 clear::All,
-                cursor::Goto(left, top),
+                cursor::Goto(layout.board.x, layout.board.y),
                 self.grid
             )
             .unwrap();
-            writeln!(self.stdout, "{}{}", cursor::Goto(left, top + 20), message).unwrap();
+            self.draw_panel(&layout);
+        }
+        self.draw_status(&layout);
+        writeln!(
+            self.stdout,
+            "{}{}",
+            cursor::Goto(layout.message.x, layout.message.y),
+            message
+        )
+        .unwrap();
+    }
+
+    /// Draw the puzzle name, clock, counters and modes under the board
+    fn draw_status(&mut self, layout: &Layout) {
+        let elapsed = self.clock.elapsed();
+        self.shown_secs = elapsed.as_secs();
+        let mut mode = format!(
+            "  {}  Moves: {}  Mistakes: {}",
+            Elapsed(elapsed),
+            self.moves,
+            self.mistakes
+        );
+        if self.notes_mode {
+            mode.push_str("  [notes]");
+        }
+        if let Some(d) = self.stamp {
+            mode.push_str(&format!("  [stamping {d}]"));
+        }
+        if self.auto_prune {
+            mode.push_str("  [auto-prune]");
+        }
+        if self.hints_used > 0 {
+            mode.push_str(&format!("  Hints: {}", self.hints_used));
+        }
+
+        let name = match self.id {
+            Some(id) => format!("Puzzle {id}"),
+            None => format!("{} puzzle", self.difficulty),
+        };
+        write!(
+            self.stdout,
+            "{}{}{}{}",
+            cursor::Goto(layout.status.x, layout.status.y),
+            name,
+            mode,
+            clear::UntilNewline
+        )
+        .unwrap();
+    }
+
+    /// Draw how many of each digit are still missing beside the board,
+    /// greying out the digits that are done
+    fn draw_panel(&mut self, layout: &Layout) {
+        let Some(panel) = layout.panel else {
+            return;
+        };
+        write!(
+            self.stdout,
+            "{}{}{:^width$}",
+            cursor::Goto(panel.x, panel.y),
+            style::Reset,
+            "Remaining",
+            width = PANEL_WIDTH as usize
+        )
+        .unwrap();
+
+        let remaining = self.grid.remaining_digits();
+        for d in 1..10 {
+            let point = layout.panel_digit(d).unwrap();
+            let marker = if self.stamp == Some(d) { '>' } else { ' ' };
+            let count = remaining[d as usize];
+            let (fg, text) = if count == 0 {
+                (color::Rgb(90, 90, 90), format!("{marker} {d}   done"))
+            } else {
+                (
+                    color::Rgb(220, 220, 220),
+                    format!("{marker} {d}   {count} left"),
+                )
+            };
+            write!(
+                self.stdout,
+                "{}{}{}{}",
+                cursor::Goto(point.x, point.y),
+                color::Fg(fg),
+                text,
+                color::Fg(color::Reset)
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn is_scroll(evt: &Event) -> bool {
+    matches!(
+        *evt,
+        Event::Mouse(MouseEvent::Press(
+            MouseButton::WheelUp | MouseButton::WheelDown,
+            _,
+            _
+        ))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    /// A game of a puzzle with its solution, with the cursor on an empty square
+    fn game() -> Game<Vec<u8>> {
+        let mut game = Game::new(io::empty(), vec![]);
+        let (i, j) = (0..81)
+            .map(|idx| (idx / 9, idx % 9))
+            .find(|&(i, j)| game.grid[i][j].is_empty())
+            .unwrap();
+        game.grid.move_cursor_to(i, j);
+        game
+    }
+
+    #[test]
+    fn scrolling_counts_one_move_once_settled() {
+        let mut game = game();
+        let (i, j) = game.grid.current();
+        let answer = game.grid.solution().unwrap()[i][j];
+
+        for _ in 0..7 {
+            game.scroll(1);
         }
+        assert_eq!(game.grid[i][j].value(), 7);
+        assert_eq!((game.moves, game.mistakes), (0, 0));
+
+        game.settle_scroll();
+        assert_eq!(game.moves, 1);
+        assert_eq!(game.mistakes, usize::from(answer != 7));
+
+        // Scrolling back to where it started isn't a move
+        game.scroll(1);
+        game.scroll(9);
+        game.settle_scroll();
+        assert_eq!(game.moves, 1);
+    }
+
+    #[test]
+    fn scrolling_leaves_squares_alone_in_notes_mode() {
+        let mut game = game();
+        let (i, j) = game.grid.current();
+        game.notes_mode = true;
+
+        game.scroll(1);
+        game.settle_scroll();
+        assert!(game.grid[i][j].is_empty());
+        assert_eq!(game.moves, 0);
     }
 }
@@ -0,0 +1,175 @@
+/// The lexicographically smallest puzzle equivalent to `values` under
+/// relabelling digits, permuting bands, stacks and the rows and columns
+/// within them, and transposing. Empty squares (0) sort first
+pub fn canonical(values: &[[u8; 9]; 9]) -> [[u8; 9]; 9] {
+    let mut best = [10; 81];
+
+    let mut transposed = [[0; 9]; 9];
+    for (i, row) in transposed.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = values[j][i];
+        }
+    }
+
+    for grid in [*values, transposed].iter() {
+        for cols in column_orders() {
+            let mut search = Search {
+                grid,
+                cols,
+                current: [0; 81],
+                best: &mut best,
+            };
+            search.place_row(0, 0, 0, [0; 10], 1);
+        }
+    }
+
+    let mut result = [[0; 9]; 9];
+    for (idx, &value) in best.iter().enumerate() {
+        result[idx / 9][idx % 9] = value;
+    }
+    result
+}
+
+/// A stable 64 bit FNV-1a hash of the canonical form
+pub fn fingerprint(values: &[[u8; 9]; 9]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for row in canonical(values).iter() {
+        for &value in row.iter() {
+            hash ^= u64::from(value);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Every order of columns reachable by permuting the stacks
+/// and the columns within each stack
+fn column_orders() -> Vec<[usize; 9]> {
+    let perms = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+
+    let mut orders = Vec::with_capacity(6 * 6 * 6 * 6);
+    for stacks in perms.iter() {
+        for first in perms.iter() {
+            for second in perms.iter() {
+                for third in perms.iter() {
+                    let within = [first, second, third];
+                    let mut order = [0; 9];
+                    for (k, col) in order.iter_mut().enumerate() {
+                        *col = stacks[k / 3] * 3 + within[k / 3][k % 3];
+                    }
+                    orders.push(order);
+                }
+            }
+        }
+    }
+    orders
+}
+
+/// Depth first search over row orders for a fixed column order,
+/// pruning as soon as the rows so far compare worse than the best found
+struct Search<'a> {
+    grid: &'a [[u8; 9]; 9],
+    cols: [usize; 9],
+    current: [u8; 81],
+    best: &'a mut [u8; 81],
+}
+
+impl<'a> Search<'a> {
+    /// Choose the row at position `pos`, given the rows used so far
+    /// as a bitmask and the digit labels assigned so far
+    fn place_row(&mut self, pos: usize, band: usize, used: u16, labels: [u8; 10], next: u8) {
+        if pos == 9 {
+            if self.current[..] < self.best[..] {
+                self.best.copy_from_slice(&self.current);
+            }
+            return;
+        }
+
+        for row in 0..9 {
+            if used & (1 << row) != 0 {
+                continue;
+            }
+            if pos.is_multiple_of(3) {
+                // Starting a new band, which must not have been used yet
+                if used & (0b111 << (row / 3 * 3)) != 0 {
+                    continue;
+                }
+            } else if row / 3 != band {
+                continue;
+            }
+
+            let mut labels = labels;
+            let mut next = next;
+            for k in 0..9 {
+                let value = self.grid[row][self.cols[k]] as usize;
+                if value != 0 && labels[value] == 0 {
+                    labels[value] = next;
+                    next += 1;
+                }
+                self.current[pos * 9 + k] = labels[value];
+            }
+
+            let end = (pos + 1) * 9;
+            if self.current[..end] > self.best[..end] {
+                continue;
+            }
+            self.place_row(pos + 1, row / 3, used | 1 << row, labels, next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::super::generator::{read_puzzles, Difficulty, Generator, PuzzleId};
+
+    #[test]
+    fn disguises_share_a_canonical_form() {
+        let mut rng = Rng::with_seed(7);
+        for puzzle in read_puzzles(Difficulty::Hard.puzzles()).iter().take(5) {
+            for quarter_turns in 0..4 {
+                let mut disguised = puzzle.clone();
+                Generator::disguise(&mut disguised, &mut rng);
+                disguised.rotate(quarter_turns);
+
+                assert_eq!(disguised.canonical().clues(), puzzle.canonical().clues());
+                assert_eq!(disguised.fingerprint(), puzzle.fingerprint());
+                assert!(disguised.is_equivalent(puzzle));
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_form_is_its_own_canonical_form() {
+        let puzzle = &read_puzzles(Difficulty::Medium.puzzles())[0];
+        let canonical = puzzle.canonical();
+        assert_eq!(canonical.canonical().clues(), canonical.clues());
+    }
+
+    #[test]
+    fn different_puzzles_have_different_fingerprints() {
+        let puzzles: Vec<_> = (0..10)
+            .map(|seed| {
+                Generator::from_id(PuzzleId {
+                    difficulty: Difficulty::Medium,
+                    seed,
+                })
+            })
+            .collect();
+
+        for (a, first) in puzzles.iter().enumerate() {
+            for second in puzzles[a + 1..].iter() {
+                assert_ne!(first.fingerprint(), second.fingerprint());
+                assert!(!first.is_equivalent(second));
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use super::solver::Solver;
+
+/// Number of constraints every solution has to satisfy exactly once:
+/// each cell, and each digit in every row, column and block
+const CONSTRAINTS: usize = 4 * 81;
+
+/// Knuth's Algorithm X over dancing links, treating sudoku as an
+/// exact cover problem of 729 candidate placements and 324 constraints
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DancingLinks;
+
+impl Solver for DancingLinks {
+    fn solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> Vec<[[u8; 9]; 9]> {
+        let mut solutions = vec![];
+        if limit == 0 {
+            return solutions;
+        }
+        if let Some(mut links) = Links::new(values) {
+            links.search(&mut |placements| {
+                solutions.push(decode(placements));
+                solutions.len() >= limit
+            });
+        }
+        solutions
+    }
+
+    fn count_solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> usize {
+        let mut count = 0;
+        if limit == 0 {
+            return count;
+        }
+        if let Some(mut links) = Links::new(values) {
+            links.search(&mut |_| {
+                count += 1;
+                count >= limit
+            });
+        }
+        count
+    }
+}
+
+/// A placement is encoded as `cell * 9 + digit - 1`
+fn decode(placements: &[usize]) -> [[u8; 9]; 9] {
+    let mut values = [[0; 9]; 9];
+    for &placement in placements {
+        let cell = placement / 9;
+        values[cell / 9][cell % 9] = (placement % 9) as u8 + 1;
+    }
+    values
+}
+
+/// The constraints satisfied by putting `digit` (0 based) at row `i`, column `j`
+fn constraints(i: usize, j: usize, digit: usize) -> [usize; 4] {
+    let block = (i / 3) * 3 + j / 3;
+    [
+        i * 9 + j,
+        81 + i * 9 + digit,
+        2 * 81 + j * 9 + digit,
+        3 * 81 + block * 9 + digit,
+    ]
+}
+
+/// Toroidal doubly linked lists stored as parallel vectors.
+/// Node 0 is the root and nodes 1 to CONSTRAINTS are the column headers
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    placement: Vec<usize>,
+    size: Vec<usize>,
+    chosen: Vec<usize>,
+}
+
+impl Links {
+    /// Returns None if the given values already contradict each other
+    fn new(values: &[[u8; 9]; 9]) -> Option<Links> {
+        let headers = CONSTRAINTS + 1;
+        let capacity = headers + 729 * 4;
+        let mut links = Links {
+            left: Vec::with_capacity(capacity),
+            right: Vec::with_capacity(capacity),
+            up: Vec::with_capacity(capacity),
+            down: Vec::with_capacity(capacity),
+            column: Vec::with_capacity(capacity),
+            placement: Vec::with_capacity(capacity),
+            size: vec![0; headers],
+            chosen: vec![],
+        };
+
+        for node in 0..headers {
+            links.left.push((node + headers - 1) % headers);
+            links.right.push((node + 1) % headers);
+            links.up.push(node);
+            links.down.push(node);
+            links.column.push(node);
+            links.placement.push(0);
+        }
+
+        let mut first_nodes = Vec::with_capacity(729);
+        for i in 0..9 {
+            for j in 0..9 {
+                for digit in 0..9 {
+                    first_nodes
+                        .push(links.add_row((i * 9 + j) * 9 + digit, constraints(i, j, digit)));
+                }
+            }
+        }
+
+        let mut covered = vec![false; headers];
+        for (i, row) in values.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if value == 0 {
+                    continue;
+                }
+                let digit = value as usize - 1;
+                for &constraint in constraints(i, j, digit).iter() {
+                    if covered[constraint + 1] {
+                        return None;
+                    }
+                    covered[constraint + 1] = true;
+                }
+
+                let first = first_nodes[(i * 9 + j) * 9 + digit];
+                links.cover(links.column[first]);
+                links.select(first);
+            }
+        }
+
+        Some(links)
+    }
+
+    /// Append a row with one node in each of the given columns,
+    /// returning the index of its first node
+    fn add_row(&mut self, placement: usize, constraints: [usize; 4]) -> usize {
+        let first = self.left.len();
+        for (k, &constraint) in constraints.iter().enumerate() {
+            let node = first + k;
+            let header = constraint + 1;
+
+            self.left.push(if k == 0 { first + 3 } else { node - 1 });
+            self.right.push(if k == 3 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.placement.push(placement);
+
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+        first
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.size[self.column[j]] += 1;
+                self.down[up] = j;
+                self.up[down] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = header;
+        self.left[right] = header;
+    }
+
+    /// Choose the row containing `node`, whose own column is already covered
+    fn select(&mut self, node: usize) {
+        self.chosen.push(self.placement[node]);
+        let mut j = self.right[node];
+        while j != node {
+            self.cover(self.column[j]);
+            j = self.right[j];
+        }
+    }
+
+    fn deselect(&mut self, node: usize) {
+        let mut j = self.left[node];
+        while j != node {
+            self.uncover(self.column[j]);
+            j = self.left[j];
+        }
+        self.chosen.pop();
+    }
+
+    /// The uncovered column with the fewest remaining rows
+    fn smallest_column(&self) -> usize {
+        let mut best = self.right[0];
+        let mut header = self.right[best];
+        while header != 0 {
+            if self.size[header] < self.size[best] {
+                best = header;
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    /// Call `visit` with the placements of every solution until it returns true.
+    /// Returns whether the search was stopped early
+    fn search<F: FnMut(&[usize]) -> bool>(&mut self, visit: &mut F) -> bool {
+        if self.right[0] == 0 {
+            return visit(&self.chosen);
+        }
+
+        let header = self.smallest_column();
+        if self.size[header] == 0 {
+            return false;
+        }
+
+        self.cover(header);
+        let mut node = self.down[header];
+        while node != header {
+            self.select(node);
+            let stop = self.search(visit);
+            self.deselect(node);
+            if stop {
+                self.uncover(header);
+                return true;
+            }
+            node = self.down[node];
+        }
+        self.uncover(header);
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::generator::{read_puzzles, DIFFICULTIES};
+    use super::super::solver::Backtracking;
+    use super::*;
+
+    /// Every solution each solver finds, in a fixed order
+    fn sorted_solutions<S: Solver>(
+        solver: &S,
+        values: &[[u8; 9]; 9],
+        limit: usize,
+    ) -> Vec<[[u8; 9]; 9]> {
+        let mut solutions = solver.solutions(values, limit);
+        solutions.sort();
+        solutions
+    }
+
+    #[test]
+    fn agrees_with_backtracking_on_seed_puzzles() {
+        for diff in DIFFICULTIES.iter() {
+            for (i, puzzle) in read_puzzles(diff.puzzles()).iter().enumerate() {
+                let values = puzzle.clues();
+                assert_eq!(
+                    DancingLinks.solutions(&values, 2),
+                    Backtracking.solutions(&values, 2),
+                    "{} puzzle #{}",
+                    diff,
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_with_backtracking_on_many_solutions() {
+        let mut values = read_puzzles(DIFFICULTIES[2].puzzles())[0].clues();
+        values[0] = [0; 9];
+        values[1] = [0; 9];
+
+        let solutions = sorted_solutions(&Backtracking, &values, 10_000);
+        assert!(solutions.len() > 1 && solutions.len() < 10_000);
+        assert_eq!(sorted_solutions(&DancingLinks, &values, 10_000), solutions);
+        assert_eq!(DancingLinks.count_solutions(&values, 10), 10);
+        assert_eq!(DancingLinks.solutions(&values, 10).len(), 10);
+    }
+
+    #[test]
+    fn finds_nothing_for_conflicting_clues() {
+        let mut values = [[0; 9]; 9];
+        values[0][0] = 5;
+        values[0][8] = 5;
+        assert!(DancingLinks.solutions(&values, 2).is_empty());
+        assert!(Backtracking.solutions(&values, 2).is_empty());
+    }
+}
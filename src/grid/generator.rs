@@ -1,6 +1,12 @@
 use std::convert::Into;
 use std::fmt;
+use std::mem;
+use std::str::FromStr;
 
+use fastrand::Rng;
+
+use super::rating;
+use super::solver::{self, Backtracking, Solver};
 use super::Grid;
 
 const VERY_EASY: &str = include_str!("./seeds/veasy.csv");
@@ -9,32 +15,188 @@ const MEDIUM: &str = include_str!("./seeds/medium.csv");
 const HARD: &str = include_str!("./seeds/hard.csv");
 const FIENDISH: &str = include_str!("./seeds/fiendish.csv");
 
+/// How many fresh puzzles to try before falling back to the seed puzzles
+const GENERATION_ATTEMPTS: usize = 20;
+
+/// Randomly chosen puzzle ids use seeds below this, to keep them short
+pub const SEED_LIMIT: u32 = 1 << 20;
+
 pub struct Generator {}
 
 impl Generator {
     pub fn generate<T: Into<Difficulty>>(diff: T) -> Grid {
-        let puzzles_str = diff.into().puzzles();
+        Generator::generate_with_rng(diff, &mut Rng::new())
+    }
+
+    /// Generate the exact puzzle described by the id
+    pub fn from_id(id: PuzzleId) -> Grid {
+        Generator::generate_with_rng(id.difficulty, &mut id.rng())
+    }
+
+    /// Generate a puzzle drawing all randomness from `rng`,
+    /// so the same seed always gives the same puzzle
+    pub fn generate_with_rng<T: Into<Difficulty>>(diff: T, rng: &mut Rng) -> Grid {
+        let diff = diff.into();
+        Generator::build_or_seed(diff, rng, |rng| {
+            Generator::from_scratch(diff, Symmetry::None, rng)
+        })
+    }
+
+    /// The first puzzle `build` comes up with in GENERATION_ATTEMPTS tries,
+    /// or a seed puzzle for `diff` if it never does
+    fn build_or_seed<F>(diff: Difficulty, rng: &mut Rng, mut build: F) -> Grid
+    where
+        F: FnMut(&mut Rng) -> Option<Grid>,
+    {
+        for _ in 0..GENERATION_ATTEMPTS {
+            if let Some(puzzle) = build(rng) {
+                return puzzle;
+            }
+        }
+
+        Generator::from_seeds(diff, rng)
+    }
+
+    /// Build a random complete grid and remove clues, along with the squares
+    /// `symmetry` pairs them with, while the puzzle stays uniquely solvable
+    /// and no harder than `diff`.
+    /// Returns None if the result doesn't end up rated as `diff`
+    pub fn from_scratch(diff: Difficulty, symmetry: Symmetry, rng: &mut Rng) -> Option<Grid> {
+        let solution = solver::random_solution(rng);
+        let mut values = solution;
+
+        let mut cells: Vec<_> = (0..81).collect();
+        rng.shuffle(&mut cells);
+
+        for idx in cells {
+            if values[idx / 9][idx % 9] == 0 {
+                continue;
+            }
+
+            let orbit = symmetry.orbit(idx);
+            let removed: Vec<u8> = orbit
+                .iter()
+                .map(|&idx| mem::replace(&mut values[idx / 9][idx % 9], 0))
+                .collect();
+
+            if Backtracking.count_solutions(&values, 2) != 1
+                || rating::rate_values(&values).difficulty() > diff
+            {
+                for (&idx, &value) in orbit.iter().zip(removed.iter()) {
+                    values[idx / 9][idx % 9] = value;
+                }
+            }
+        }
+
+        if rating::rate_values(&values).difficulty() == diff {
+            Some(Grid::with_solution(values, solution))
+        } else {
+            None
+        }
+    }
+
+    /// Pick one of the seed puzzles for `diff` and disguise it
+    pub fn from_seeds(diff: Difficulty, rng: &mut Rng) -> Grid {
+        let puzzles_str = diff.puzzles();
         let puzzles = read_puzzles(puzzles_str);
 
-        let mut puzzle = puzzles[fastrand::usize(..puzzles.len())].clone();
+        let mut puzzle = Grid::playable(puzzles[rng.usize(..puzzles.len())].clues());
+        Generator::disguise(&mut puzzle, rng);
 
+        puzzle
+    }
+
+    /// Apply a random element of the sudoku symmetry group: relabel the
+    /// digits, reorder the bands, the stacks and the rows and columns
+    /// within them, and maybe transpose
+    pub fn disguise(puzzle: &mut Grid, rng: &mut Rng) {
         let mut permutation: Vec<_> = (1..10).collect();
-        fastrand::shuffle(&mut permutation);
+        rng.shuffle(&mut permutation);
         puzzle.permute(&permutation);
 
-        if fastrand::bool() {
-            puzzle.flip_horizontally();
+        shuffle_three(rng, |a, b| puzzle.swap_bands(a, b));
+        shuffle_three(rng, |a, b| puzzle.swap_stacks(a, b));
+        for k in 0..3 {
+            shuffle_three(rng, |a, b| puzzle.swap_rows(k, a, b));
+            shuffle_three(rng, |a, b| puzzle.swap_cols(k, a, b));
         }
 
-        if fastrand::bool() {
-            puzzle.flip_vertically();
+        if rng.bool() {
+            puzzle.transpose();
         }
+    }
+}
 
-        puzzle
+/// Uniformly shuffle three things given a way to swap two of them
+fn shuffle_three<F: FnMut(usize, usize)>(rng: &mut Rng, mut swap: F) {
+    for k in (1..3).rev() {
+        let other = rng.usize(..=k);
+        if other != k {
+            swap(other, k);
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Every difficulty, easiest first
+pub const DIFFICULTIES: [Difficulty; 5] = [
+    Difficulty::VeryEasy,
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Fiendish,
+];
+
+/// A pattern the clues of a puzzle can be required to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// The same after turning the board half way round
+    Rotational,
+    /// The same after flipping the board left to right
+    Mirror,
+}
+
+impl Symmetry {
+    /// The squares, as indices from 0 to 80, that have to be
+    /// kept or removed along with square `idx`
+    pub fn orbit(self, idx: usize) -> Vec<usize> {
+        let other = match self {
+            Symmetry::None => idx,
+            Symmetry::Rotational => 80 - idx,
+            Symmetry::Mirror => idx / 9 * 9 + 8 - idx % 9,
+        };
+        if other == idx {
+            vec![idx]
+        } else {
+            vec![idx, other]
+        }
+    }
+
+    /// Whether the clues follow the pattern
+    pub fn matches(self, values: &[[u8; 9]; 9]) -> bool {
+        (0..81).all(|idx| {
+            let clue = values[idx / 9][idx % 9] != 0;
+            self.orbit(idx)
+                .iter()
+                .all(|&other| (values[other / 9][other % 9] != 0) == clue)
+        })
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Symmetry, String> {
+        match s {
+            "none" => Ok(Symmetry::None),
+            "rotational" => Ok(Symmetry::Rotational),
+            "mirror" => Ok(Symmetry::Mirror),
+            _ => Err(format!("Unknown symmetry: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Difficulty {
     VeryEasy,
     Easy,
@@ -53,6 +215,28 @@ impl Difficulty {
             Difficulty::Fiendish => FIENDISH,
         }
     }
+
+    /// Single letter used in puzzle ids
+    pub fn letter(self) -> char {
+        match self {
+            Difficulty::VeryEasy => 'V',
+            Difficulty::Easy => 'E',
+            Difficulty::Medium => 'M',
+            Difficulty::Hard => 'H',
+            Difficulty::Fiendish => 'F',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Difficulty> {
+        match letter.to_ascii_uppercase() {
+            'V' => Some(Difficulty::VeryEasy),
+            'E' => Some(Difficulty::Easy),
+            'M' => Some(Difficulty::Medium),
+            'H' => Some(Difficulty::Hard),
+            'F' => Some(Difficulty::Fiendish),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> From<&'a str> for Difficulty {
@@ -68,6 +252,28 @@ impl<'a> From<&'a str> for Difficulty {
     }
 }
 
+impl FromStr for Difficulty {
+    type Err = String;
+
+    /// Parse a name like "hard" or "very-easy", or a single letter like "H"
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        let name = s.trim().to_lowercase().replace(['-', '_'], " ");
+        let mut letters = name.chars();
+        match (letters.next(), letters.next()) {
+            (Some(letter), None) => Difficulty::from_letter(letter),
+            _ => match name.as_str() {
+                "very easy" | "veryeasy" => Some(Difficulty::VeryEasy),
+                "easy" => Some(Difficulty::Easy),
+                "medium" => Some(Difficulty::Medium),
+                "hard" => Some(Difficulty::Hard),
+                "fiendish" => Some(Difficulty::Fiendish),
+                _ => None,
+            },
+        }
+        .ok_or_else(|| format!("Unknown difficulty: {s}"))
+    }
+}
+
 impl fmt::Display for Difficulty {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str_rep = match *self {
@@ -81,13 +287,144 @@ impl fmt::Display for Difficulty {
     }
 }
 
-fn read_puzzles(puzzles_str: &'static str) -> Vec<Grid> {
+/// A shareable name for a generated puzzle, written like `H-3f9a2`.
+/// The seed drives every random choice made by the generator, so it
+/// pins down the seed puzzle picked and the transform applied to it
+/// just as well as a puzzle built from scratch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PuzzleId {
+    pub difficulty: Difficulty,
+    pub seed: u32,
+}
+
+impl PuzzleId {
+    pub fn random(difficulty: Difficulty) -> PuzzleId {
+        PuzzleId {
+            difficulty,
+            seed: fastrand::u32(..SEED_LIMIT),
+        }
+    }
+
+    /// The random number generator behind the puzzle. The difficulty is
+    /// mixed in so ids differing only in difficulty don't start from
+    /// the same solution and clue order
+    pub fn rng(self) -> Rng {
+        Rng::with_seed(u64::from(self.difficulty.letter()) << 32 | u64::from(self.seed))
+    }
+}
+
+impl fmt::Display for PuzzleId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{:x}", self.difficulty.letter(), self.seed)
+    }
+}
+
+impl FromStr for PuzzleId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PuzzleId, String> {
+        let invalid = || format!("Invalid puzzle id: {s}");
+
+        let mut parts = s.trim().splitn(2, '-');
+        let letter = parts.next().ok_or_else(invalid)?;
+        let seed = parts.next().ok_or_else(invalid)?;
+
+        let mut letters = letter.chars();
+        let difficulty = match (letters.next(), letters.next()) {
+            (Some(letter), None) => Difficulty::from_letter(letter).ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        let seed = u32::from_str_radix(seed, 16).map_err(|_| invalid())?;
+
+        Ok(PuzzleId { difficulty, seed })
+    }
+}
+
+pub fn read_puzzles(puzzles_str: &'static str) -> Vec<Grid> {
     let mut puzzles = vec![];
     let lines: Vec<_> = puzzles_str.lines().collect();
 
     for i in 0..lines.len() / 9 {
-        puzzles.push(Grid::from_csv(lines[i * 9..i * 9 + 9].join("\n").as_str()));
+        puzzles.push(Grid::from_csv(lines[i * 9..i * 9 + 9].join("\n").as_str()).unwrap());
     }
 
     puzzles
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_puzzles_have_unique_solutions() {
+        let difficulties = [
+            Difficulty::VeryEasy,
+            Difficulty::Easy,
+            Difficulty::Medium,
+            Difficulty::Hard,
+            Difficulty::Fiendish,
+        ];
+
+        for diff in difficulties.iter() {
+            for (i, puzzle) in read_puzzles(diff.puzzles()).iter().enumerate() {
+                assert!(
+                    puzzle.has_unique_solution(),
+                    "{} puzzle #{} does not have a unique solution",
+                    diff,
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_unrelated_puzzles_for_each_difficulty() {
+        let solutions: Vec<_> = DIFFICULTIES[..3]
+            .iter()
+            .map(|&difficulty| {
+                Generator::from_id(PuzzleId {
+                    difficulty,
+                    seed: 1,
+                })
+                .solution()
+            })
+            .collect();
+
+        assert_ne!(solutions[0], solutions[1]);
+        assert_ne!(solutions[1], solutions[2]);
+        assert_ne!(solutions[0], solutions[2]);
+    }
+
+    #[test]
+    fn puzzles_from_scratch_are_unique_and_rated_as_asked() {
+        for &diff in DIFFICULTIES.iter() {
+            let built: Vec<Grid> = (0..10)
+                .filter_map(|seed| {
+                    Generator::from_scratch(diff, Symmetry::None, &mut Rng::with_seed(seed))
+                })
+                .collect();
+
+            assert!(!built.is_empty(), "no {} puzzle built", diff);
+            for puzzle in built.iter() {
+                assert!(puzzle.has_unique_solution(), "{}", puzzle.to_line());
+                assert_eq!(rating::rate(puzzle).difficulty(), diff);
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_to_seed_puzzles() {
+        for &diff in DIFFICULTIES.iter() {
+            let mut tries = 0;
+            let puzzle = Generator::build_or_seed(diff, &mut Rng::with_seed(1), |_| {
+                tries += 1;
+                None
+            });
+
+            assert_eq!(tries, GENERATION_ATTEMPTS);
+            assert!(read_puzzles(diff.puzzles())
+                .iter()
+                .any(|seed| seed.is_equivalent(&puzzle)));
+        }
+    }
+}
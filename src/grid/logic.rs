@@ -0,0 +1,912 @@
+use std::fmt;
+
+use super::solver::ALL_DIGITS;
+
+/// Position of a square as (row, column)
+pub type Cell = (usize, usize);
+
+/// Solving techniques, ordered from easiest to hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    PointingPair,
+    BoxLineReduction,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XYWing,
+    SimpleColoring,
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str_rep = match *self {
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::NakedSingle => "Naked Single",
+            Technique::PointingPair => "Pointing Pair",
+            Technique::BoxLineReduction => "Box/Line Reduction",
+            Technique::NakedPair => "Naked Pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::XYWing => "XY-Wing",
+            Technique::SimpleColoring => "Simple Coloring",
+        };
+        write!(f, "{str_rep}")
+    }
+}
+
+/// A single deduction: the digits it places, the candidates it rules out
+/// and the squares that justify it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<(usize, usize, u8)>,
+    pub eliminations: Vec<(usize, usize, u8)>,
+    pub reason_cells: Vec<Cell>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.technique)?;
+        for &(i, j, value) in self.placements.iter() {
+            write!(f, " put {} in r{}c{}", value, i + 1, j + 1)?;
+        }
+
+        let mut values: Vec<u8> = self.eliminations.iter().map(|&(_, _, v)| v).collect();
+        values.sort();
+        values.dedup();
+        for (k, &value) in values.iter().enumerate() {
+            let cells: Vec<String> = self
+                .eliminations
+                .iter()
+                .filter(|&&(_, _, v)| v == value)
+                .map(|&(i, j, _)| format!("r{}c{}", i + 1, j + 1))
+                .collect();
+            let sep = if k == 0 { "" } else { ";" };
+            write!(f, "{} remove {} from {}", sep, value, cells.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves puzzles one step at a time using only techniques
+/// a human could reasonably apply with pencil marks
+#[derive(Debug, Clone)]
+pub struct LogicalSolver {
+    values: [[u8; 9]; 9],
+    candidates: [[u16; 9]; 9],
+}
+
+impl LogicalSolver {
+    pub fn new(values: &[[u8; 9]; 9]) -> Self {
+        let mut solver = LogicalSolver {
+            values: [[0; 9]; 9],
+            candidates: [[ALL_DIGITS; 9]; 9],
+        };
+
+        for (i, row) in values.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if value != 0 {
+                    solver.place(i, j, value);
+                }
+            }
+        }
+
+        solver
+    }
+
+    pub fn values(&self) -> [[u8; 9]; 9] {
+        self.values
+    }
+
+    /// Bitmask of the digits still possible at the given square
+    pub fn candidates(&self, i: usize, j: usize) -> u16 {
+        self.candidates[i][j]
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|row| row.iter().all(|&v| v != 0))
+    }
+
+    /// Find the easiest step that makes progress,
+    /// or None if the puzzle is solved or beyond the known techniques
+    pub fn next_step(&self) -> Option<Step> {
+        if self.is_stuck() {
+            return None;
+        }
+
+        self.hidden_single()
+            .or_else(|| self.naked_single())
+            .or_else(|| self.pointing())
+            .or_else(|| self.box_line_reduction())
+            .or_else(|| self.naked_subset(2))
+            .or_else(|| self.fish(2))
+            .or_else(|| self.hidden_subset(2))
+            .or_else(|| self.naked_subset(3))
+            .or_else(|| self.fish(3))
+            .or_else(|| self.hidden_subset(3))
+            .or_else(|| self.xy_wing())
+            .or_else(|| self.simple_coloring())
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for &(i, j, value) in step.placements.iter() {
+            self.place(i, j, value);
+        }
+        for &(i, j, value) in step.eliminations.iter() {
+            self.candidates[i][j] &= !(1 << value);
+        }
+    }
+
+    /// Apply steps until the puzzle is solved or no technique applies,
+    /// returning every step taken
+    pub fn solve(&mut self) -> Vec<Step> {
+        let mut steps = vec![];
+        while let Some(step) = self.next_step() {
+            self.apply(&step);
+            steps.push(step);
+        }
+        steps
+    }
+
+    fn place(&mut self, i: usize, j: usize, value: u8) {
+        self.values[i][j] = value;
+        self.candidates[i][j] = 0;
+        for (pi, pj) in peers((i, j)) {
+            self.candidates[pi][pj] &= !(1 << value);
+        }
+    }
+
+    /// True if some empty square has no candidates left
+    fn is_stuck(&self) -> bool {
+        (0..9).any(|i| (0..9).any(|j| self.values[i][j] == 0 && self.candidates[i][j] == 0))
+    }
+
+    fn empty_cells(&self, unit: usize) -> Vec<Cell> {
+        unit_cells(unit)
+            .iter()
+            .cloned()
+            .filter(|&(i, j)| self.values[i][j] == 0)
+            .collect()
+    }
+
+    /// Empty squares of the unit that could still hold `value`
+    fn positions(&self, unit: usize, value: u8) -> Vec<Cell> {
+        unit_cells(unit)
+            .iter()
+            .cloned()
+            .filter(|&(i, j)| self.candidates[i][j] & (1 << value) != 0)
+            .collect()
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        for i in 0..9 {
+            for j in 0..9 {
+                let candidates = self.candidates[i][j];
+                if self.values[i][j] == 0 && candidates.count_ones() == 1 {
+                    return Some(Step {
+                        technique: Technique::NakedSingle,
+                        placements: vec![(i, j, candidates.trailing_zeros() as u8)],
+                        eliminations: vec![],
+                        reason_cells: peers((i, j))
+                            .into_iter()
+                            .filter(|&(pi, pj)| self.values[pi][pj] != 0)
+                            .collect(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in 0..27 {
+            for value in 1..10 {
+                let positions = self.positions(unit, value);
+                if positions.len() == 1 {
+                    let (i, j) = positions[0];
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        placements: vec![(i, j, value)],
+                        eliminations: vec![],
+                        reason_cells: unit_cells(unit).to_vec(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Candidates of a block confined to one row or column
+    /// rule that digit out of the rest of the line
+    fn pointing(&self) -> Option<Step> {
+        for block in 18..27 {
+            for value in 1..10 {
+                let positions = self.positions(block, value);
+                if positions.len() < 2 {
+                    continue;
+                }
+                for line in lines_through(&positions) {
+                    let eliminations = self.eliminate_outside(line, &positions, value);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::PointingPair,
+                            placements: vec![],
+                            eliminations,
+                            reason_cells: positions,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Candidates of a row or column confined to one block
+    /// rule that digit out of the rest of the block
+    fn box_line_reduction(&self) -> Option<Step> {
+        for line in 0..18 {
+            for value in 1..10 {
+                let positions = self.positions(line, value);
+                if positions.len() < 2 {
+                    continue;
+                }
+                let (i, j) = positions[0];
+                let block = block_unit(i, j);
+                if !positions
+                    .iter()
+                    .all(|&(pi, pj)| block_unit(pi, pj) == block)
+                {
+                    continue;
+                }
+                let eliminations = self.eliminate_outside(block, &positions, value);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::BoxLineReduction,
+                        placements: vec![],
+                        eliminations,
+                        reason_cells: positions,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// `size` squares of a unit sharing exactly `size` candidates
+    /// rule those digits out of the rest of the unit
+    fn naked_subset(&self, size: usize) -> Option<Step> {
+        let technique = if size == 2 {
+            Technique::NakedPair
+        } else {
+            Technique::NakedTriple
+        };
+
+        for unit in 0..27 {
+            let cells = self.empty_cells(unit);
+            let small: Vec<Cell> = cells
+                .iter()
+                .cloned()
+                .filter(|&(i, j)| {
+                    let count = self.candidates[i][j].count_ones() as usize;
+                    count >= 2 && count <= size
+                })
+                .collect();
+
+            for combination in combinations(small.len(), size) {
+                let subset: Vec<Cell> = combination.iter().map(|&k| small[k]).collect();
+                let mask = subset
+                    .iter()
+                    .fold(0, |mask, &(i, j)| mask | self.candidates[i][j]);
+                if mask.count_ones() as usize != size {
+                    continue;
+                }
+
+                let mut eliminations = vec![];
+                for &(i, j) in cells.iter().filter(|cell| !subset.contains(cell)) {
+                    for value in digits(self.candidates[i][j] & mask) {
+                        eliminations.push((i, j, value));
+                    }
+                }
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique,
+                        placements: vec![],
+                        eliminations,
+                        reason_cells: subset,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// `size` digits confined to the same `size` squares of a unit
+    /// rule every other candidate out of those squares
+    fn hidden_subset(&self, size: usize) -> Option<Step> {
+        let technique = if size == 2 {
+            Technique::HiddenPair
+        } else {
+            Technique::HiddenTriple
+        };
+
+        for unit in 0..27 {
+            let values: Vec<u8> = (1..10)
+                .filter(|&value| {
+                    let count = self.positions(unit, value).len();
+                    count >= 2 && count <= size
+                })
+                .collect();
+
+            for combination in combinations(values.len(), size) {
+                let mask = combination
+                    .iter()
+                    .fold(0u16, |mask, &k| mask | 1 << values[k]);
+                let cells: Vec<Cell> = unit_cells(unit)
+                    .iter()
+                    .cloned()
+                    .filter(|&(i, j)| self.candidates[i][j] & mask != 0)
+                    .collect();
+                if cells.len() != size {
+                    continue;
+                }
+
+                let mut eliminations = vec![];
+                for &(i, j) in cells.iter() {
+                    for value in digits(self.candidates[i][j] & !mask) {
+                        eliminations.push((i, j, value));
+                    }
+                }
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique,
+                        placements: vec![],
+                        eliminations,
+                        reason_cells: cells,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// X-Wing for `size` 2 and Swordfish for `size` 3: a digit confined
+    /// to the same `size` columns in `size` rows (or vice versa) is ruled
+    /// out of the rest of those columns
+    fn fish(&self, size: usize) -> Option<Step> {
+        let technique = if size == 2 {
+            Technique::XWing
+        } else {
+            Technique::Swordfish
+        };
+
+        for value in 1..10 {
+            for &(base, cover) in [(0, 9), (9, 0)].iter() {
+                let lines: Vec<usize> = (0..9)
+                    .filter(|&line| {
+                        let count = self.positions(base + line, value).len();
+                        count >= 2 && count <= size
+                    })
+                    .collect();
+
+                for combination in combinations(lines.len(), size) {
+                    let base_lines: Vec<usize> = combination.iter().map(|&k| lines[k]).collect();
+                    let mut reason_cells = vec![];
+                    let mut crossing = 0u16;
+                    for &line in base_lines.iter() {
+                        for cell in self.positions(base + line, value) {
+                            crossing |= 1 << cross_index(cell, base);
+                            reason_cells.push(cell);
+                        }
+                    }
+                    if crossing.count_ones() as usize != size {
+                        continue;
+                    }
+
+                    let mut eliminations = vec![];
+                    for cross in 0..9 {
+                        if crossing & (1 << cross) == 0 {
+                            continue;
+                        }
+                        for (i, j) in self.positions(cover + cross, value) {
+                            if !reason_cells.contains(&(i, j)) {
+                                eliminations.push((i, j, value));
+                            }
+                        }
+                    }
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique,
+                            placements: vec![],
+                            eliminations,
+                            reason_cells,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A pivot with candidates XY seeing pincers XZ and YZ:
+    /// whichever way the pivot goes one pincer is Z, so no
+    /// square seeing both pincers can be Z
+    fn xy_wing(&self) -> Option<Step> {
+        let mut bivalue = vec![];
+        for i in 0..9 {
+            for j in 0..9 {
+                if self.candidates[i][j].count_ones() == 2 {
+                    bivalue.push((i, j));
+                }
+            }
+        }
+
+        for &pivot in bivalue.iter() {
+            let pivot_mask = self.candidates[pivot.0][pivot.1];
+            for &a in bivalue.iter().filter(|&&a| sees(pivot, a)) {
+                let a_mask = self.candidates[a.0][a.1];
+                if (a_mask & pivot_mask).count_ones() != 1 {
+                    continue;
+                }
+                let z = a_mask & !pivot_mask;
+
+                for &b in bivalue.iter().filter(|&&b| b != a && sees(pivot, b)) {
+                    let b_mask = self.candidates[b.0][b.1];
+                    if b_mask & !pivot_mask != z || b_mask & a_mask != z {
+                        continue;
+                    }
+
+                    let value = z.trailing_zeros() as u8;
+                    let mut eliminations = vec![];
+                    for i in 0..9 {
+                        for j in 0..9 {
+                            let cell = (i, j);
+                            if self.candidates[i][j] & z != 0
+                                && cell != pivot
+                                && sees(cell, a)
+                                && sees(cell, b)
+                            {
+                                eliminations.push((i, j, value));
+                            }
+                        }
+                    }
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XYWing,
+                            placements: vec![],
+                            eliminations,
+                            reason_cells: vec![pivot, a, b],
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Chains of conjugate pairs for a digit alternate between two colors,
+    /// one of which must be true. A color seeing itself is false, and any
+    /// square seeing both colors can't hold the digit
+    fn simple_coloring(&self) -> Option<Step> {
+        for value in 1..10 {
+            let mut links: Vec<(Cell, Cell)> = vec![];
+            for unit in 0..27 {
+                let positions = self.positions(unit, value);
+                if positions.len() == 2 {
+                    links.push((positions[0], positions[1]));
+                }
+            }
+
+            let mut colors: [[Option<(usize, bool)>; 9]; 9] = [[None; 9]; 9];
+            let mut chains = 0;
+            for &(start, _) in links.iter() {
+                if colors[start.0][start.1].is_some() {
+                    continue;
+                }
+                let chain = chains;
+                chains += 1;
+
+                let mut members = vec![(start, true)];
+                let mut stack = vec![(start, true)];
+                colors[start.0][start.1] = Some((chain, true));
+                while let Some((cell, color)) = stack.pop() {
+                    for &(a, b) in links.iter() {
+                        let other = if a == cell {
+                            b
+                        } else if b == cell {
+                            a
+                        } else {
+                            continue;
+                        };
+                        if colors[other.0][other.1].is_none() {
+                            colors[other.0][other.1] = Some((chain, !color));
+                            members.push((other, !color));
+                            stack.push((other, !color));
+                        }
+                    }
+                }
+
+                let reason_cells: Vec<Cell> = members.iter().map(|&(cell, _)| cell).collect();
+
+                // Color wrap
+                for &color in [true, false].iter() {
+                    let same: Vec<Cell> = members
+                        .iter()
+                        .filter(|&&(_, c)| c == color)
+                        .map(|&(cell, _)| cell)
+                        .collect();
+                    let contradicts = same.iter().any(|&a| same.iter().any(|&b| sees(a, b)));
+                    if contradicts {
+                        return Some(Step {
+                            technique: Technique::SimpleColoring,
+                            placements: vec![],
+                            eliminations: same.iter().map(|&(i, j)| (i, j, value)).collect(),
+                            reason_cells,
+                        });
+                    }
+                }
+
+                // Color trap
+                let mut eliminations = vec![];
+                for i in 0..9 {
+                    for j in 0..9 {
+                        if self.candidates[i][j] & (1 << value) == 0
+                            || reason_cells.contains(&(i, j))
+                        {
+                            continue;
+                        }
+                        let sees_color = |color: bool| {
+                            members
+                                .iter()
+                                .any(|&(cell, c)| c == color && sees(cell, (i, j)))
+                        };
+                        if sees_color(true) && sees_color(false) {
+                            eliminations.push((i, j, value));
+                        }
+                    }
+                }
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::SimpleColoring,
+                        placements: vec![],
+                        eliminations,
+                        reason_cells,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Rule `value` out of every square of the unit apart from `keep`
+    fn eliminate_outside(&self, unit: usize, keep: &[Cell], value: u8) -> Vec<(usize, usize, u8)> {
+        self.positions(unit, value)
+            .into_iter()
+            .filter(|cell| !keep.contains(cell))
+            .map(|(i, j)| (i, j, value))
+            .collect()
+    }
+}
+
+/// Units 0 to 8 are rows, 9 to 17 columns and 18 to 26 blocks
+fn unit_cells(unit: usize) -> [Cell; 9] {
+    let mut cells = [(0, 0); 9];
+    for (k, cell) in cells.iter_mut().enumerate() {
+        *cell = match unit {
+            0..=8 => (unit, k),
+            9..=17 => (k, unit - 9),
+            _ => {
+                let block = unit - 18;
+                ((block / 3) * 3 + k / 3, (block % 3) * 3 + k % 3)
+            }
+        };
+    }
+    cells
+}
+
+fn block_unit(i: usize, j: usize) -> usize {
+    18 + (i / 3) * 3 + j / 3
+}
+
+/// The position of a cell along the lines crossing the `base` lines
+fn cross_index((i, j): Cell, base: usize) -> usize {
+    if base == 0 {
+        j
+    } else {
+        i
+    }
+}
+
+/// The row and column units containing all of the given cells
+fn lines_through(cells: &[Cell]) -> Vec<usize> {
+    let mut lines = vec![];
+    let (i, j) = cells[0];
+    if cells.iter().all(|&(pi, _)| pi == i) {
+        lines.push(i);
+    }
+    if cells.iter().all(|&(_, pj)| pj == j) {
+        lines.push(9 + j);
+    }
+    lines
+}
+
+/// Whether two different squares share a row, column or block
+pub fn sees(a: Cell, b: Cell) -> bool {
+    a != b && (a.0 == b.0 || a.1 == b.1 || block_unit(a.0, a.1) == block_unit(b.0, b.1))
+}
+
+/// Squares sharing a row, column or block with `cell`, excluding itself
+pub fn peers(cell: Cell) -> Vec<Cell> {
+    let mut peers = Vec::with_capacity(20);
+    for i in 0..9 {
+        for j in 0..9 {
+            if sees(cell, (i, j)) {
+                peers.push((i, j));
+            }
+        }
+    }
+    peers
+}
+
+fn digits(mask: u16) -> impl Iterator<Item = u8> {
+    (1..10).filter(move |&value| mask & (1 << value) != 0)
+}
+
+/// Every way of choosing `k` of the indices `0..n`, in increasing order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn extend(
+        start: usize,
+        n: usize,
+        k: usize,
+        current: &mut Vec<usize>,
+        all: &mut Vec<Vec<usize>>,
+    ) {
+        if current.len() == k {
+            all.push(current.clone());
+            return;
+        }
+        for idx in start..n {
+            current.push(idx);
+            extend(idx + 1, n, k, current, all);
+            current.pop();
+        }
+    }
+
+    let mut all = vec![];
+    extend(0, n, k, &mut vec![], &mut all);
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::generator::{read_puzzles, DIFFICULTIES};
+    use super::super::solver::{Backtracking, Solver};
+    use super::*;
+
+    /// An empty board where every square can still be anything,
+    /// to build positions on by removing candidates
+    fn open_board() -> LogicalSolver {
+        LogicalSolver::new(&[[0; 9]; 9])
+    }
+
+    fn remove(solver: &mut LogicalSolver, cells: &[Cell], value: u8) {
+        for &(i, j) in cells {
+            solver.candidates[i][j] &= !(1 << value);
+        }
+    }
+
+    fn restrict(solver: &mut LogicalSolver, (i, j): Cell, values: &[u8]) {
+        solver.candidates[i][j] = values.iter().fold(0, |mask, &value| mask | 1 << value);
+    }
+
+    fn row(i: usize, cols: &[usize]) -> Vec<Cell> {
+        cols.iter().map(|&j| (i, j)).collect()
+    }
+
+    fn col(j: usize, rows: &[usize]) -> Vec<Cell> {
+        rows.iter().map(|&i| (i, j)).collect()
+    }
+
+    /// Every square of `cells` losing each of `values`, sorted
+    fn expected(cells: &[Cell], values: &[u8]) -> Vec<(usize, usize, u8)> {
+        let mut eliminations: Vec<_> = cells
+            .iter()
+            .flat_map(|&(i, j)| values.iter().map(move |&value| (i, j, value)))
+            .collect();
+        eliminations.sort();
+        eliminations
+    }
+
+    fn check(step: Option<Step>, technique: Technique, eliminations: Vec<(usize, usize, u8)>) {
+        let mut step = step.expect("no step found");
+        step.eliminations.sort();
+        assert_eq!(step.technique, technique);
+        assert!(step.placements.is_empty());
+        assert_eq!(step.eliminations, eliminations);
+    }
+
+    #[test]
+    fn steps_agree_with_solution_on_seed_puzzles() {
+        for diff in DIFFICULTIES.iter() {
+            for (n, puzzle) in read_puzzles(diff.puzzles()).iter().enumerate() {
+                let clues = puzzle.clues();
+                let solution = Backtracking.solve(&clues).unwrap();
+                let mut solver = LogicalSolver::new(&clues);
+
+                while let Some(step) = solver.next_step() {
+                    for &(i, j, value) in step.placements.iter() {
+                        assert_eq!(solution[i][j], value, "{} #{}: {}", diff, n, step);
+                    }
+                    for &(i, j, value) in step.eliminations.iter() {
+                        assert_ne!(solution[i][j], value, "{} #{}: {}", diff, n, step);
+                    }
+                    solver.apply(&step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hidden_single() {
+        let mut solver = open_board();
+        remove(&mut solver, &row(0, &[1, 2, 3, 4, 5, 6, 7, 8]), 5);
+
+        let step = solver.next_step().unwrap();
+        assert_eq!(step.technique, Technique::HiddenSingle);
+        assert_eq!(step.placements, vec![(0, 0, 5)]);
+    }
+
+    #[test]
+    fn naked_single() {
+        let mut solver = open_board();
+        restrict(&mut solver, (4, 4), &[7]);
+
+        let step = solver.naked_single().unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.placements, vec![(4, 4, 7)]);
+    }
+
+    #[test]
+    fn pointing_pair() {
+        let mut solver = open_board();
+        let mut rest = row(0, &[2]);
+        rest.extend(row(1, &[0, 1, 2]));
+        rest.extend(row(2, &[0, 1, 2]));
+        remove(&mut solver, &rest, 3);
+
+        check(
+            solver.pointing(),
+            Technique::PointingPair,
+            expected(&row(0, &[3, 4, 5, 6, 7, 8]), &[3]),
+        );
+    }
+
+    #[test]
+    fn box_line_reduction() {
+        let mut solver = open_board();
+        remove(&mut solver, &row(0, &[2, 3, 4, 5, 6, 7, 8]), 4);
+
+        let mut rest = row(1, &[0, 1, 2]);
+        rest.extend(row(2, &[0, 1, 2]));
+        check(
+            solver.box_line_reduction(),
+            Technique::BoxLineReduction,
+            expected(&rest, &[4]),
+        );
+    }
+
+    #[test]
+    fn naked_pair() {
+        let mut solver = open_board();
+        restrict(&mut solver, (0, 0), &[1, 2]);
+        restrict(&mut solver, (0, 1), &[1, 2]);
+
+        check(
+            solver.naked_subset(2),
+            Technique::NakedPair,
+            expected(&row(0, &[2, 3, 4, 5, 6, 7, 8]), &[1, 2]),
+        );
+    }
+
+    #[test]
+    fn naked_triple() {
+        let mut solver = open_board();
+        restrict(&mut solver, (0, 0), &[1, 2]);
+        restrict(&mut solver, (0, 1), &[2, 3]);
+        restrict(&mut solver, (0, 2), &[1, 3]);
+
+        check(
+            solver.naked_subset(3),
+            Technique::NakedTriple,
+            expected(&row(0, &[3, 4, 5, 6, 7, 8]), &[1, 2, 3]),
+        );
+    }
+
+    #[test]
+    fn hidden_pair() {
+        let mut solver = open_board();
+        for value in 1..3 {
+            remove(&mut solver, &row(0, &[2, 3, 4, 5, 6, 7, 8]), value);
+        }
+
+        check(
+            solver.hidden_subset(2),
+            Technique::HiddenPair,
+            expected(&row(0, &[0, 1]), &[3, 4, 5, 6, 7, 8, 9]),
+        );
+    }
+
+    #[test]
+    fn hidden_triple() {
+        let mut solver = open_board();
+        for value in 1..4 {
+            remove(&mut solver, &row(0, &[3, 4, 5, 6, 7, 8]), value);
+        }
+
+        check(
+            solver.hidden_subset(3),
+            Technique::HiddenTriple,
+            expected(&row(0, &[0, 1, 2]), &[4, 5, 6, 7, 8, 9]),
+        );
+    }
+
+    #[test]
+    fn x_wing() {
+        let mut solver = open_board();
+        remove(&mut solver, &row(1, &[0, 1, 3, 4, 5, 6, 8]), 6);
+        remove(&mut solver, &row(5, &[0, 1, 3, 4, 5, 6, 8]), 6);
+
+        let mut rest = col(2, &[0, 2, 3, 4, 6, 7, 8]);
+        rest.extend(col(7, &[0, 2, 3, 4, 6, 7, 8]));
+        check(solver.fish(2), Technique::XWing, expected(&rest, &[6]));
+    }
+
+    #[test]
+    fn swordfish() {
+        let mut solver = open_board();
+        remove(&mut solver, &row(0, &[0, 2, 3, 5, 6, 7, 8]), 8);
+        remove(&mut solver, &row(4, &[0, 1, 2, 3, 5, 6, 8]), 8);
+        remove(&mut solver, &row(8, &[0, 2, 3, 4, 5, 6, 8]), 8);
+
+        let others = [1, 2, 3, 5, 6, 7];
+        let mut rest = col(1, &others);
+        rest.extend(col(4, &others));
+        rest.extend(col(7, &others));
+        check(solver.fish(3), Technique::Swordfish, expected(&rest, &[8]));
+    }
+
+    #[test]
+    fn xy_wing() {
+        let mut solver = open_board();
+        restrict(&mut solver, (0, 0), &[1, 2]);
+        restrict(&mut solver, (0, 4), &[1, 3]);
+        restrict(&mut solver, (4, 0), &[2, 3]);
+
+        check(
+            solver.xy_wing(),
+            Technique::XYWing,
+            expected(&[(4, 4)], &[3]),
+        );
+    }
+
+    #[test]
+    fn simple_coloring() {
+        let mut solver = open_board();
+        remove(&mut solver, &row(0, &[1, 2, 3, 5, 6, 7, 8]), 9);
+        remove(&mut solver, &col(0, &[1, 2, 3, 5, 6, 7, 8]), 9);
+        remove(&mut solver, &col(4, &[1, 2, 3, 5, 6, 7, 8]), 9);
+
+        check(
+            solver.simple_coloring(),
+            Technique::SimpleColoring,
+            expected(&row(4, &[1, 2, 3, 5, 6, 7, 8]), &[9]),
+        );
+    }
+}
@@ -2,28 +2,50 @@ use termion::color;
 use termion::cursor;
 use termion::style;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Write;
 use std::ops::Index;
 
+use format;
+
 pub mod square;
 pub use self::square::Square;
 
+pub mod canonical;
+pub mod dlx;
 pub mod generator;
+pub mod logic;
+pub mod rating;
+mod save;
+pub mod solver;
+pub use self::solver::Solver;
+pub mod theme;
+pub use self::theme::Theme;
+
+/// Size of the board drawn by the Display impl, in terminal cells.
+/// Every square is 5 wide and 3 tall to fit a 3x3 grid of notes
+pub const BOARD_WIDTH: u16 = 55;
+pub const BOARD_HEIGHT: u16 = 37;
 
 const BORDER_COLOR: color::Fg<color::Rgb> = color::Fg(color::Rgb(220, 220, 220));
+const NOTES_COLOR: color::Fg<color::Rgb> = color::Fg(color::Rgb(150, 150, 150));
 
-const BORDER_TOP: &str = "┏━━━┯━━━┯━━━┳━━━┯━━━┯━━━┳━━━┯━━━┯━━━┓";
-const BORDER_BOTTOM: &str = "┗━━━┷━━━┷━━━┻━━━┷━━━┷━━━┻━━━┷━━━┷━━━┛";
-const BORDER_HORIZONTAL_THIN: &str = "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨";
-const BORDER_HORIZONTAL_THICK: &str = "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫";
+const BORDER_TOP: &str = "┏━━━━━┯━━━━━┯━━━━━┳━━━━━┯━━━━━┯━━━━━┳━━━━━┯━━━━━┯━━━━━┓";
+const BORDER_BOTTOM: &str = "┗━━━━━┷━━━━━┷━━━━━┻━━━━━┷━━━━━┷━━━━━┻━━━━━┷━━━━━┷━━━━━┛";
+const BORDER_HORIZONTAL_THIN: &str = "┠─────┼─────┼─────╂─────┼─────┼─────╂─────┼─────┼─────┨";
+const BORDER_HORIZONTAL_THICK: &str = "┣━━━━━┿━━━━━┿━━━━━╋━━━━━┿━━━━━┿━━━━━╋━━━━━┿━━━━━┿━━━━━┫";
 const BORDER_VERTICAL_THICK: &str = "┃";
 const BORDER_VERTICAL_THIN: &str = "│";
 
 #[derive(Debug, Clone)]
 struct GridState {
     squares: [[Square; 9]; 9],
+    /// Candidate notes for each square, with bit `d` set for digit `d`
+    notes: [[u16; 9]; 9],
+    /// A solution of the puzzle given by the initial squares,
+    /// all zeros if it has none
+    solution: [[u8; 9]; 9],
     current: (usize, usize),
 }
 
@@ -32,25 +54,63 @@ pub struct Grid {
     state: GridState,
     past: Vec<GridState>,
     future: Vec<GridState>,
+    /// Remove a digit from the notes of its peers whenever it's placed
+    auto_prune: bool,
+    /// Squares drawn with a highlighted background, e.g. for hints
+    highlighted: HashSet<(usize, usize)>,
+    /// Squares found to disagree with the solution by the last check,
+    /// with the value each of them had then
+    checked: HashMap<(usize, usize), u8>,
+    theme: Theme,
+    /// Digit to highlight instead of the one under the cursor
+    selected_digit: Option<u8>,
 }
 
 impl Grid {
+    /// The puzzle with no solution stored, which is all most commands need
     pub fn new(values: [[u8; 9]; 9]) -> Self {
-        Grid {
-            state: GridState::new(values),
-            past: vec![],
-            future: vec![],
-        }
+        Grid::with_solution(values, [[0; 9]; 9])
+    }
+
+    /// The puzzle set up for playing, solving it once up front so moves
+    /// can be checked and revealed
+    pub fn playable(values: [[u8; 9]; 9]) -> Self {
+        let solution = solver::Backtracking.solve(&values).unwrap_or([[0; 9]; 9]);
+        Grid::with_solution(values, solution)
     }
 
-    pub fn from_csv(csv: &str) -> Self {
+    /// The puzzle along with its solution, all zeros if there's none
+    pub fn with_solution(values: [[u8; 9]; 9], solution: [[u8; 9]; 9]) -> Self {
+        let mut state = GridState::new(values);
+        state.solution = solution;
         Grid {
-            state: GridState::from_csv(csv),
+            state,
             past: vec![],
             future: vec![],
+            auto_prune: false,
+            highlighted: HashSet::new(),
+            checked: HashMap::new(),
+            theme: Theme::default(),
+            selected_digit: None,
         }
     }
 
+    /// Read a puzzle written as 9 lines of comma separated digits
+    pub fn from_csv(csv: &str) -> Result<Self, String> {
+        format::parse_csv(csv).map(Grid::new)
+    }
+
+    /// Read a puzzle written on one line like `..3.2.6..9..3.5..1...`,
+    /// with `.`, `0` or `_` for empty squares
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        format::parse_line(line).map(Grid::new)
+    }
+
+    /// The puzzle on one line, with `.` for empty squares
+    pub fn to_line(&self) -> String {
+        format::write_line(&self.clues())
+    }
+
     pub fn move_cursor(&mut self, dir: Direction) {
         self.past.push(self.state.clone());
         self.state.move_cursor(dir);
@@ -60,9 +120,59 @@ impl Grid {
     pub fn update_current(&mut self, d: usize) {
         self.past.push(self.state.clone());
         self.state.update_current(d);
+        if self.auto_prune && d != 0 {
+            let (i, j) = self.state.current;
+            if !self.state.squares[i][j].is_initial() {
+                self.state.prune_notes(i, j, d);
+            }
+        }
+        self.future = vec![];
+    }
+
+    pub fn move_cursor_to(&mut self, i: usize, j: usize) {
+        self.past.push(self.state.clone());
+        self.state.current = (i, j);
+        self.future = vec![];
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Highlight `digit` rather than whatever is under the cursor,
+    /// or go back to following the cursor with None
+    pub fn select_digit(&mut self, digit: Option<u8>) {
+        self.selected_digit = digit;
+    }
+
+    /// Row and column of the cursor
+    pub fn current(&self) -> (usize, usize) {
+        self.state.current
+    }
+
+    pub fn set_auto_prune(&mut self, auto_prune: bool) {
+        self.auto_prune = auto_prune;
+    }
+
+    /// Note every digit not already used in each empty square's row, column and block
+    pub fn fill_notes(&mut self) {
+        self.past.push(self.state.clone());
+        self.state.fill_notes();
+        self.future = vec![];
+    }
+
+    /// Toggle `d` in the notes of the current square, or clear them if `d` is 0
+    pub fn toggle_note(&mut self, d: usize) {
+        self.past.push(self.state.clone());
+        self.state.toggle_note(d);
         self.future = vec![];
     }
 
+    /// Bitmask of the digits noted in the square
+    pub fn notes(&self, i: usize, j: usize) -> u16 {
+        self.state.notes[i][j]
+    }
+
     pub fn freeze(&mut self) {
         self.state.freeze();
     }
@@ -79,6 +189,33 @@ impl Grid {
         self.state.flip_vertically();
     }
 
+    pub fn transpose(&mut self) {
+        self.state.transpose();
+    }
+
+    /// Rotate clockwise by the given number of quarter turns
+    pub fn rotate(&mut self, quarter_turns: usize) {
+        self.state.rotate(quarter_turns);
+    }
+
+    /// Swap rows `a` and `b` (0 to 2) of the given band
+    pub fn swap_rows(&mut self, band: usize, a: usize, b: usize) {
+        self.state.swap_rows(band, a, b);
+    }
+
+    /// Swap columns `a` and `b` (0 to 2) of the given stack
+    pub fn swap_cols(&mut self, stack: usize, a: usize, b: usize) {
+        self.state.swap_cols(stack, a, b);
+    }
+
+    pub fn swap_bands(&mut self, a: usize, b: usize) {
+        self.state.swap_bands(a, b);
+    }
+
+    pub fn swap_stacks(&mut self, a: usize, b: usize) {
+        self.state.swap_stacks(a, b);
+    }
+
     pub fn row(&self, row: usize) -> Vec<Square> {
         self.state.row(row)
     }
@@ -91,6 +228,21 @@ impl Grid {
         self.state.block(y, x)
     }
 
+    /// How many more of each digit the board needs, indexed by digit
+    pub fn remaining_digits(&self) -> [usize; 10] {
+        let mut remaining = [9_usize; 10];
+        remaining[0] = 0;
+        for i in 0..9 {
+            for sq in self.row(i) {
+                let value = sq.value() as usize;
+                if value != 0 {
+                    remaining[value] = remaining[value].saturating_sub(1);
+                }
+            }
+        }
+        remaining
+    }
+
     pub fn remove_filled(&mut self) {
         self.past.push(self.state.clone());
         self.state.remove_filled();
@@ -120,6 +272,171 @@ impl Grid {
     pub fn find_invalid_squares(&self) -> HashSet<(usize, usize)> {
         self.state.find_invalid_squares()
     }
+
+    /// Count the solutions of the puzzle given by the initial squares,
+    /// stopping early once `limit` of them have been found
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.count_solutions_with(&solver::Backtracking, limit)
+    }
+
+    pub fn count_solutions_with<S: Solver>(&self, solver: &S, limit: usize) -> usize {
+        solver.count_solutions(&self.state.clues(), limit)
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    /// Values of the initial squares, with 0 for everything else
+    pub fn clues(&self) -> [[u8; 9]; 9] {
+        self.state.clues()
+    }
+
+    /// The representative of this puzzle's equivalence class under
+    /// digit relabelling, row/column/band/stack permutations and transposing
+    pub fn canonical(&self) -> Grid {
+        Grid::new(canonical::canonical(&self.state.clues()))
+    }
+
+    /// Whether the two puzzles are the same one in disguise
+    pub fn is_equivalent(&self, other: &Grid) -> bool {
+        canonical::canonical(&self.state.clues()) == canonical::canonical(&other.state.clues())
+    }
+
+    /// A stable identifier shared by every disguise of this puzzle
+    pub fn fingerprint(&self) -> u64 {
+        canonical::fingerprint(&self.state.clues())
+    }
+
+    /// Solve the puzzle the way a human would, returning each step taken.
+    /// Stops early if the puzzle needs techniques beyond the known ones
+    pub fn explain(&self) -> Vec<logic::Step> {
+        logic::LogicalSolver::new(&self.state.clues()).solve()
+    }
+
+    /// The easiest logical step from the current position, taking the
+    /// player's own digits as given, apart from any the solution disagrees
+    /// with, so that a wrong digit can't lead to a wrong hint
+    pub fn next_step(&self) -> Option<logic::Step> {
+        let mut values = self.state.values();
+        for (i, j) in self.wrong_squares() {
+            values[i][j] = 0;
+        }
+        logic::LogicalSolver::new(&values).next_step()
+    }
+
+    /// Values of every square, with 0 for empty ones
+    pub fn values(&self) -> [[u8; 9]; 9] {
+        self.state.values()
+    }
+
+    pub fn highlight(&mut self, cells: HashSet<(usize, usize)>) {
+        self.highlighted = cells;
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.highlighted.clear();
+    }
+
+    /// The solution stored with the puzzle, or None if it has none
+    pub fn solution(&self) -> Option<[[u8; 9]; 9]> {
+        if self.state.solution[0][0] == 0 {
+            None
+        } else {
+            Some(self.state.solution)
+        }
+    }
+
+    /// Compare every filled square with the solution and mark the ones
+    /// that are wrong, which stay marked until they're changed.
+    /// Returns their locations
+    pub fn check(&mut self) -> HashSet<(usize, usize)> {
+        let wrong = self.wrong_squares();
+        self.checked = wrong
+            .iter()
+            .map(|&(i, j)| ((i, j), self.state.squares[i][j].value()))
+            .collect();
+        wrong
+    }
+
+    /// Squares marked by the last check that still have the value
+    /// they were marked with
+    fn marked_wrong(&self) -> HashSet<(usize, usize)> {
+        self.checked
+            .iter()
+            .filter(|&(&(i, j), &value)| self.state.squares[i][j].value() == value)
+            .map(|(&cell, _)| cell)
+            .collect()
+    }
+
+    /// Filled squares whose value differs from the solution
+    fn wrong_squares(&self) -> HashSet<(usize, usize)> {
+        let mut wrong = HashSet::new();
+        if self.solution().is_none() {
+            return wrong;
+        }
+
+        for (i, row) in self.state.squares.iter().enumerate() {
+            for (j, sq) in row.iter().enumerate() {
+                if !sq.is_empty() && sq.value() != self.state.solution[i][j] {
+                    wrong.insert((i, j));
+                }
+            }
+        }
+        wrong
+    }
+
+    /// Fill the current square with its value from the solution.
+    /// Returns false if there was nothing to reveal
+    pub fn reveal_current(&mut self) -> bool {
+        let (i, j) = self.state.current;
+        let value = self.state.solution[i][j];
+        if value == 0 || self.state.squares[i][j].is_initial() {
+            return false;
+        }
+        if self.state.squares[i][j].value() != value {
+            self.update_current(value as usize);
+        }
+        true
+    }
+
+    /// Fill in the whole solution and freeze the board.
+    /// Returns false if the puzzle has no solution
+    pub fn reveal_solution(&mut self) -> bool {
+        let Some(solution) = self.solution() else {
+            return false;
+        };
+        self.past.push(self.state.clone());
+        self.state.fill(&solution);
+        self.state.freeze();
+        self.future = vec![];
+        self.checked.clear();
+        true
+    }
+
+    /// Solve the puzzle given by the initial squares,
+    /// ignoring anything the player has filled in.
+    /// Returns None if the puzzle has no solution
+    pub fn solve(&self) -> Option<Grid> {
+        self.solve_with(&solver::Backtracking)
+    }
+
+    pub fn solve_with<S: Solver>(&self, solver: &S) -> Option<Grid> {
+        let solution = solver.solve(&self.state.clues())?;
+        let mut state = self.state.clone();
+        state.fill(&solution);
+
+        Some(Grid {
+            state,
+            past: vec![],
+            future: vec![],
+            auto_prune: false,
+            highlighted: HashSet::new(),
+            checked: HashMap::new(),
+            theme: Theme::default(),
+            selected_digit: None,
+        })
+    }
 }
 
 impl GridState {
@@ -137,30 +454,12 @@ impl GridState {
 
         GridState {
             squares,
+            notes: [[0; 9]; 9],
+            solution: [[0; 9]; 9],
             current: (0, 0),
         }
     }
 
-    pub fn from_csv(csv: &str) -> Self {
-        let mut values = [[0; 9]; 9];
-        let mut i = 0;
-        let mut j = 0;
-        for c in csv.chars() {
-            if c == ',' {
-                j += 1;
-            } else if c == '\n' {
-                i += 1;
-                j = 0;
-            } else if c.is_ascii_digit() {
-                values[i][j] = c as u8 - b'0';
-            } else {
-                panic!("Unknown character in csv: {}", c);
-            }
-        }
-
-        GridState::new(values)
-    }
-
     pub fn move_cursor(&mut self, dir: Direction) {
         let (i, j) = dir.coords();
         let (ci, cj) = self.current;
@@ -177,6 +476,59 @@ impl GridState {
         }
     }
 
+    pub fn toggle_note(&mut self, d: usize) {
+        let (i, j) = self.current;
+
+        if !self.squares[i][j].is_initial() {
+            if d == 0 {
+                self.notes[i][j] = 0;
+            } else {
+                self.notes[i][j] ^= 1 << d;
+            }
+        }
+    }
+
+    pub fn fill_notes(&mut self) {
+        for i in 0..9 {
+            for j in 0..9 {
+                if self.squares[i][j].is_empty() {
+                    self.notes[i][j] = self.candidates(i, j);
+                }
+            }
+        }
+    }
+
+    /// Remove `d` from the notes of every square sharing a unit with (i, j)
+    pub fn prune_notes(&mut self, i: usize, j: usize, d: usize) {
+        for (i2, j2) in logic::peers((i, j)) {
+            self.notes[i2][j2] &= !(1 << d);
+        }
+    }
+
+    /// Bitmask of the digits not yet used by any peer of the square
+    pub fn candidates(&self, i: usize, j: usize) -> u16 {
+        let mut candidates = solver::ALL_DIGITS;
+        for (i2, j2) in logic::peers((i, j)) {
+            candidates &= !(1 << self.squares[i2][j2].value());
+        }
+        candidates
+    }
+
+    /// One of the three lines of a square's notes, like "1   3"
+    fn notes_line(&self, i: usize, j: usize, line: usize) -> String {
+        let digits: Vec<String> = (1..4)
+            .map(|k| line * 3 + k)
+            .map(|d| {
+                if self.notes[i][j] & (1 << d) != 0 {
+                    d.to_string()
+                } else {
+                    " ".to_string()
+                }
+            })
+            .collect();
+        digits.join(" ")
+    }
+
     pub fn freeze(&mut self) {
         for i in 0..9 {
             for j in 0..9 {
@@ -188,25 +540,79 @@ impl GridState {
     pub fn permute(&mut self, permutation: &[u8]) {
         assert_eq!(permutation.len(), 9);
         assert!((1..10).all(|n| permutation.contains(&n)));
+        let relabel = |value: u8| permutation[(value - 1) as usize];
 
         for i in 0..9 {
             for j in 0..9 {
-                if !self.squares[i][j].is_empty() {
-                    let value = self.squares[i][j].value();
-                    self.squares[i][j] = Square::initial(permutation[(value - 1) as usize]);
+                self.squares[i][j] = match self.squares[i][j] {
+                    Square::Initial(value) => Square::Initial(relabel(value)),
+                    Square::Filled(value) => Square::Filled(relabel(value)),
+                    Square::Empty => Square::Empty,
+                };
+                self.notes[i][j] = (1..10)
+                    .filter(|&d| self.notes[i][j] & (1 << d) != 0)
+                    .fold(0, |notes, d| notes | 1 << relabel(d));
+                if self.solution[i][j] != 0 {
+                    self.solution[i][j] = relabel(self.solution[i][j]);
                 }
             }
         }
     }
 
-    pub fn flip_horizontally(&mut self) {
+    /// Move the squares, notes, solution and cursor so that
+    /// square (i, j) ends up with what was at `source(i, j)`
+    fn remap<F: Fn(usize, usize) -> (usize, usize)>(&mut self, source: F) {
+        let squares = self.squares;
+        let notes = self.notes;
+        let solution = self.solution;
+        let current = self.current;
+
         for i in 0..9 {
-            self.squares[i].reverse();
+            for j in 0..9 {
+                let (si, sj) = source(i, j);
+                self.squares[i][j] = squares[si][sj];
+                self.notes[i][j] = notes[si][sj];
+                self.solution[i][j] = solution[si][sj];
+                if (si, sj) == current {
+                    self.current = (i, j);
+                }
+            }
         }
     }
 
+    pub fn flip_horizontally(&mut self) {
+        self.remap(|i, j| (i, 8 - j));
+    }
+
     pub fn flip_vertically(&mut self) {
-        self.squares.reverse();
+        self.remap(|i, j| (8 - i, j));
+    }
+
+    pub fn transpose(&mut self) {
+        self.remap(|i, j| (j, i));
+    }
+
+    pub fn rotate(&mut self, quarter_turns: usize) {
+        for _ in 0..quarter_turns % 4 {
+            self.transpose();
+            self.flip_horizontally();
+        }
+    }
+
+    pub fn swap_rows(&mut self, band: usize, a: usize, b: usize) {
+        self.remap(|i, j| (swapped(i, band * 3 + a, band * 3 + b), j));
+    }
+
+    pub fn swap_cols(&mut self, stack: usize, a: usize, b: usize) {
+        self.remap(|i, j| (i, swapped(j, stack * 3 + a, stack * 3 + b)));
+    }
+
+    pub fn swap_bands(&mut self, a: usize, b: usize) {
+        self.remap(|i, j| (swapped(i / 3, a, b) * 3 + i % 3, j));
+    }
+
+    pub fn swap_stacks(&mut self, a: usize, b: usize) {
+        self.remap(|i, j| (i, swapped(j / 3, a, b) * 3 + j % 3));
     }
 
     pub fn row(&self, row: usize) -> Vec<Square> {
@@ -238,6 +644,41 @@ impl GridState {
             for j in 0..9 {
                 if !self.squares[i][j].is_initial() {
                     self.squares[i][j] = Square::Empty;
+                    self.notes[i][j] = 0;
+                }
+            }
+        }
+    }
+
+    pub fn values(&self) -> [[u8; 9]; 9] {
+        let mut values = [[0; 9]; 9];
+        for (row, squares) in values.iter_mut().zip(self.squares.iter()) {
+            for (value, sq) in row.iter_mut().zip(squares.iter()) {
+                *value = sq.value();
+            }
+        }
+        values
+    }
+
+    /// Values of the initial squares, with 0 for everything else
+    pub fn clues(&self) -> [[u8; 9]; 9] {
+        let mut values = [[0; 9]; 9];
+        for (i, row) in self.squares.iter().enumerate() {
+            for (j, sq) in row.iter().enumerate() {
+                if sq.is_initial() {
+                    values[i][j] = sq.value();
+                }
+            }
+        }
+        values
+    }
+
+    /// Overwrite every non-initial square with the given values
+    pub fn fill(&mut self, values: &[[u8; 9]; 9]) {
+        for (row, values) in self.squares.iter_mut().zip(values) {
+            for (sq, &value) in row.iter_mut().zip(values) {
+                if !sq.is_initial() {
+                    *sq = Square::from_value(value);
                 }
             }
         }
@@ -258,45 +699,7 @@ impl GridState {
     /// Check the grid for inaccuracies
     /// and return the problem square locations
     pub fn find_invalid_squares(&self) -> HashSet<(usize, usize)> {
-        let mut set = HashSet::new();
-
-        for i in 0..9 {
-            for j in 0..9 {
-                let value = self.squares[i][j].value();
-                if value != 0 {
-                    // Check the column
-                    for i2 in i + 1..9 {
-                        if self.squares[i2][j].value() == value {
-                            set.insert((i, j));
-                            set.insert((i2, j));
-                        }
-                    }
-
-                    // Check the row
-                    for j2 in j + 1..9 {
-                        if self.squares[i][j2].value() == value {
-                            set.insert((i, j));
-                            set.insert((i, j2));
-                        }
-                    }
-
-                    // Check the square
-                    for i2 in prev_multiple(3, i)..next_multiple(3, i) {
-                        for j2 in prev_multiple(3, j)..next_multiple(3, j) {
-                            if i2 == i && j2 == j {
-                                continue;
-                            };
-                            if self.squares[i2][j2].value() == value {
-                                set.insert((i, j));
-                                set.insert((i2, j2));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        set
+        conflicting_squares(&self.values())
     }
 }
 
@@ -318,49 +721,105 @@ impl Index<usize> for GridState {
 
 impl fmt::Display for Grid {
     fn fmt(&self, ff: &mut fmt::Formatter) -> fmt::Result {
-        self.state.fmt(ff)
+        let wrong = self.marked_wrong();
+        let (i, j) = self.state.current;
+        let digit = self
+            .selected_digit
+            .unwrap_or(self.state.squares[i][j].value());
+        self.state
+            .draw(ff, &self.theme, digit, &self.highlighted, &wrong)
     }
 }
 
-impl fmt::Display for GridState {
-    fn fmt(&self, ff: &mut fmt::Formatter) -> fmt::Result {
-        let mistakes = self.find_invalid_squares();
+impl GridState {
+    /// Draw the board, highlighting `digit` (0 for none),
+    /// with `wrong` squares shown as mistakes
+    /// alongside the ones that conflict with each other
+    fn draw(
+        &self,
+        ff: &mut fmt::Formatter,
+        theme: &Theme,
+        digit: u8,
+        highlighted: &HashSet<(usize, usize)>,
+        wrong: &HashSet<(usize, usize)>,
+    ) -> fmt::Result {
+        let mut mistakes = self.find_invalid_squares();
+        mistakes.extend(wrong);
+        let (ci, cj) = self.current;
         let mut f = String::new();
+        let next_line = format!("{}{}", cursor::Down(1), cursor::Left(BOARD_WIDTH));
 
-        write!(f, "{BORDER_COLOR}{BORDER_TOP}")?;
-        write!(f, "{}{}", cursor::Down(1), cursor::Left(37))?;
+        write!(f, "{BORDER_COLOR}{BORDER_TOP}{next_line}")?;
         for i in 0..9 {
-            write!(f, "{BORDER_COLOR}{BORDER_VERTICAL_THICK}")?;
-            for j in 0..9 {
-                let st = if (i, j) == self.current {
-                    format!("{}", style::Invert)
-                } else {
-                    String::new()
-                };
+            for line in 0..3 {
+                write!(f, "{BORDER_COLOR}{BORDER_VERTICAL_THICK}")?;
+                for j in 0..9 {
+                    let st = if (i, j) == self.current {
+                        format!("{}", style::Invert)
+                    } else {
+                        String::new()
+                    };
 
-                let nt = if (i, j) == self.current {
-                    format!("{}", style::NoInvert)
-                } else {
-                    String::new()
-                };
+                    let nt = if (i, j) == self.current {
+                        format!("{}", style::NoInvert)
+                    } else {
+                        String::new()
+                    };
 
-                let fg = if mistakes.contains(&(i, j)) {
-                    format!("{}", color::Fg(color::Red))
-                } else if self.squares[i][j].is_initial() {
-                    format!("{}", color::Fg(color::Cyan))
-                } else {
-                    format!("{}", color::Fg(color::White))
-                };
+                    let square = self.squares[i][j];
+                    let fg = if square.is_empty() {
+                        format!("{NOTES_COLOR}")
+                    } else if mistakes.contains(&(i, j)) {
+                        format!("{}", color::Fg(color::Red))
+                    } else if square.is_initial() {
+                        format!("{}", color::Fg(color::Cyan))
+                    } else {
+                        format!("{}", color::Fg(color::White))
+                    };
 
-                write!(f, " {}{}{}{} ", st, fg, self.squares[i][j], nt)?;
-                write!(f, "{BORDER_COLOR}")?;
-                if j % 3 == 2 {
-                    write!(f, "{BORDER_VERTICAL_THICK}")?;
-                } else {
-                    write!(f, "{BORDER_VERTICAL_THIN}")?;
+                    let text = if square.is_empty() {
+                        self.notes_line(i, j, line)
+                    } else if line == 1 {
+                        format!("  {square}  ")
+                    } else {
+                        "     ".to_string()
+                    };
+
+                    let background = if highlighted.contains(&(i, j)) {
+                        Some(theme.hint)
+                    } else if theme.same_digit.enabled && digit != 0 && square.value() == digit {
+                        Some(theme.same_digit.color)
+                    } else if theme.candidates.enabled
+                        && digit != 0
+                        && square.is_empty()
+                        && self.notes[i][j] & (1 << digit) != 0
+                    {
+                        Some(theme.candidates.color)
+                    } else if theme.peers.enabled
+                        && (i == ci || j == cj || (i / 3 == ci / 3 && j / 3 == cj / 3))
+                    {
+                        Some(theme.peers.color)
+                    } else {
+                        None
+                    };
+                    let (bg, nbg) = match background {
+                        Some(rgb) => (
+                            format!("{}", color::Bg(rgb)),
+                            format!("{}", color::Bg(color::Reset)),
+                        ),
+                        None => (String::new(), String::new()),
+                    };
+
+                    write!(f, "{st}{bg}{fg}{text}{nbg}{nt}")?;
+                    write!(f, "{BORDER_COLOR}")?;
+                    if j % 3 == 2 {
+                        write!(f, "{BORDER_VERTICAL_THICK}")?;
+                    } else {
+                        write!(f, "{BORDER_VERTICAL_THIN}")?;
+                    }
                 }
+                write!(f, "{next_line}")?;
             }
-            write!(f, "{}{}", cursor::Down(1), cursor::Left(37))?;
             write!(f, "{BORDER_COLOR}")?;
             if i == 8 {
                 write!(f, "{BORDER_BOTTOM}")?;
@@ -369,18 +828,41 @@ impl fmt::Display for GridState {
             } else {
                 write!(f, "{BORDER_HORIZONTAL_THIN}")?;
             }
-            write!(f, "{}{}", cursor::Down(1), cursor::Left(37))?;
+            write!(f, "{next_line}")?;
         }
         write!(ff, "{f}")
     }
 }
 
-fn prev_multiple(a: usize, b: usize) -> usize {
-    b - (b % a)
+/// Squares whose value is repeated in their row, column or block
+pub fn conflicting_squares(values: &[[u8; 9]; 9]) -> HashSet<(usize, usize)> {
+    let mut set = HashSet::new();
+
+    for i in 0..9 {
+        for j in 0..9 {
+            let value = values[i][j];
+            if value != 0
+                && logic::peers((i, j))
+                    .iter()
+                    .any(|&(i2, j2)| values[i2][j2] == value)
+            {
+                set.insert((i, j));
+            }
+        }
+    }
+
+    set
 }
 
-fn next_multiple(a: usize, b: usize) -> usize {
-    b + a - (b % a)
+/// `k`, or the other one of `a` and `b` if it's one of them
+fn swapped(k: usize, a: usize, b: usize) -> usize {
+    if k == a {
+        b
+    } else if k == b {
+        a
+    } else {
+        k
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -401,3 +883,246 @@ impl Direction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::generator::{read_puzzles, Difficulty};
+    use super::*;
+    use format::Format;
+
+    fn medium_puzzles() -> Vec<Grid> {
+        read_puzzles(Difficulty::Medium.puzzles())
+            .iter()
+            .map(|puzzle| Grid::playable(puzzle.clues()))
+            .collect()
+    }
+
+    /// A puzzle with notes and the cursor on one of its empty squares
+    fn marked_state() -> (GridState, (usize, usize)) {
+        let mut state = medium_puzzles()[0].state.clone();
+        let cell = (0..81)
+            .map(|idx| (idx / 9, idx % 9))
+            .find(|&(i, j)| state.squares[i][j].is_empty())
+            .unwrap();
+        state.current = cell;
+        state.notes[cell.0][cell.1] = 1 << 4 | 1 << 7;
+        (state, cell)
+    }
+
+    #[test]
+    fn notes_and_cursor_follow_transforms() {
+        let transforms: Vec<fn(&mut GridState)> = vec![
+            GridState::transpose,
+            GridState::flip_horizontally,
+            GridState::flip_vertically,
+            |state| state.rotate(1),
+            |state| state.swap_rows(0, 0, 2),
+            |state| state.swap_cols(0, 1, 2),
+            |state| state.swap_bands(0, 1),
+            |state| state.swap_stacks(0, 2),
+        ];
+
+        for transform in transforms {
+            let (before, (bi, bj)) = marked_state();
+            let mut state = before.clone();
+            transform(&mut state);
+
+            let (i, j) = state.current;
+            assert_eq!(state.notes[i][j], before.notes[bi][bj]);
+            assert_eq!(state.solution[i][j], before.solution[bi][bj]);
+            assert!(state.squares[i][j].is_empty());
+            let noted = state.notes.iter().flatten().filter(|&&n| n != 0).count();
+            assert_eq!(noted, 1);
+        }
+    }
+
+    #[test]
+    fn hints_ignore_wrong_digits() {
+        for puzzle in medium_puzzles().iter() {
+            let mut grid = puzzle.clone();
+            let solution = grid.solution().unwrap();
+
+            // A wrong digit that doesn't conflict with anything
+            let (i, j, value) = (0..81)
+                .map(|idx| (idx / 9, idx % 9))
+                .filter(|&(i, j)| grid.state.squares[i][j].is_empty())
+                .flat_map(|(i, j)| (1..10).map(move |value| (i, j, value)))
+                .find(|&(i, j, value)| {
+                    value != solution[i][j] && grid.state.candidates(i, j) & (1 << value) != 0
+                })
+                .unwrap();
+            grid.state.squares[i][j] = Square::from_value(value);
+
+            let step = grid.next_step().unwrap();
+            for &(i, j, value) in step.placements.iter() {
+                assert_eq!(solution[i][j], value, "{}", step);
+            }
+            for &(i, j, value) in step.eliminations.iter() {
+                assert_ne!(solution[i][j], value, "{}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn filling_notes_undoes_in_one_step() {
+        let mut grid = medium_puzzles()[0].clone();
+        grid.fill_notes();
+
+        for (i, j) in (0..81).map(|idx| (idx / 9, idx % 9)) {
+            let expected = if grid.state.squares[i][j].is_empty() {
+                grid.state.candidates(i, j)
+            } else {
+                0
+            };
+            assert_eq!(grid.notes(i, j), expected);
+        }
+
+        grid.undo();
+        assert_eq!(grid.state.notes, [[0; 9]; 9]);
+        assert!(grid.past.is_empty());
+    }
+
+    #[test]
+    fn auto_prune_only_touches_peers_and_undoes_in_one_step() {
+        let mut grid = medium_puzzles()[0].clone();
+        let solution = grid.solution().unwrap();
+        grid.set_auto_prune(true);
+        grid.fill_notes();
+        let (ci, cj) = marked_state().1;
+        grid.move_cursor_to(ci, cj);
+        let before = grid.state.clone();
+
+        let d = solution[ci][cj];
+        grid.update_current(d as usize);
+
+        let peers = logic::peers((ci, cj));
+        for (i, j) in (0..81).map(|idx| (idx / 9, idx % 9)) {
+            let notes = before.notes[i][j];
+            if peers.contains(&(i, j)) {
+                assert_eq!(grid.notes(i, j), notes & !(1 << d));
+            } else {
+                assert_eq!(grid.notes(i, j), notes);
+            }
+        }
+
+        grid.undo();
+        assert_eq!(grid.state.squares, before.squares);
+        assert_eq!(grid.state.notes, before.notes);
+        assert_eq!(grid.state.current, before.current);
+    }
+
+    #[test]
+    fn check_marks_wrong_squares_until_they_change() {
+        let mut grid = medium_puzzles()[0].clone();
+        let solution = grid.solution().unwrap();
+        let (i, j) = marked_state().1;
+        let wrong = |value: u8| value != solution[i][j];
+        let (first, second) = {
+            let mut values = (1..10).filter(|&value| wrong(value));
+            (values.next().unwrap(), values.next().unwrap())
+        };
+
+        grid.move_cursor_to(i, j);
+        assert!(grid.check().is_empty());
+        grid.update_current(first as usize);
+        assert_eq!(grid.check(), [(i, j)].iter().cloned().collect());
+        assert_eq!(grid.marked_wrong(), grid.check());
+
+        grid.update_current(second as usize);
+        assert!(grid.marked_wrong().is_empty());
+        grid.update_current(solution[i][j] as usize);
+        assert!(grid.check().is_empty());
+    }
+
+    #[test]
+    fn reveal_current_fills_in_one_square() {
+        let mut grid = medium_puzzles()[0].clone();
+        let solution = grid.solution().unwrap();
+        let (i, j) = marked_state().1;
+
+        grid.move_cursor_to(i, j);
+        let before = grid.values();
+        assert!(grid.reveal_current());
+        assert_eq!(grid[i][j].value(), solution[i][j]);
+        assert!(!grid[i][j].is_initial());
+        let changed = (0..81)
+            .filter(|&idx| grid.values()[idx / 9][idx % 9] != before[idx / 9][idx % 9])
+            .count();
+        assert_eq!(changed, 1);
+
+        grid.undo();
+        assert_eq!(grid.values(), before);
+
+        let clue = (0..81)
+            .map(|idx| (idx / 9, idx % 9))
+            .find(|&(i, j)| grid[i][j].is_initial())
+            .unwrap();
+        grid.move_cursor_to(clue.0, clue.1);
+        assert!(!grid.reveal_current());
+        assert!(!Grid::new(grid.clues()).reveal_current());
+    }
+
+    #[test]
+    fn reveal_solution_freezes_the_solved_board() {
+        let mut grid = medium_puzzles()[0].clone();
+        let solution = grid.solution().unwrap();
+        let (i, j) = marked_state().1;
+        grid.move_cursor_to(i, j);
+        grid.update_current(solution[i][j] as usize % 9 + 1);
+        grid.check();
+
+        assert!(grid.reveal_solution());
+        assert!(grid.is_solved());
+        assert_eq!(grid.values(), solution);
+        assert!(grid.marked_wrong().is_empty());
+        assert!((0..81).all(|idx| grid[idx / 9][idx % 9].is_initial()));
+
+        // Nothing can be changed once it's frozen
+        grid.update_current(0);
+        assert_eq!(grid.values(), solution);
+
+        assert!(!Grid::new(grid.clues()).reveal_solution());
+    }
+
+    #[test]
+    fn puzzles_round_trip_through_text() {
+        let puzzle = &read_puzzles(Difficulty::Hard.puzzles())[0];
+        let line = puzzle.to_line();
+        assert_eq!(line.len(), 81);
+        assert_eq!(Grid::from_line(&line).unwrap().clues(), puzzle.clues());
+
+        let csv = format::write_puzzle(&puzzle.clues(), Format::Csv);
+        assert_eq!(Grid::from_csv(&csv).unwrap().clues(), puzzle.clues());
+        assert_eq!(Grid::from_csv(&csv).unwrap().to_line(), line);
+    }
+
+    #[test]
+    fn malformed_puzzles_are_rejected() {
+        let puzzle = &read_puzzles(Difficulty::Hard.puzzles())[0];
+        let line = puzzle.to_line();
+        for bad in [&line[1..], &line.replacen('.', "x", 1)].iter() {
+            assert!(Grid::from_line(bad).is_err(), "{}", bad);
+        }
+
+        let csv = format::write_puzzle(&puzzle.clues(), Format::Csv);
+        let missing_row: Vec<&str> = csv.lines().skip(1).collect();
+        for bad in [
+            missing_row.join("\n"),
+            csv.replacen(',', ",,", 1),
+            csv.replacen(',', ",x,", 1),
+            csv.replacen(',', ",10,", 1),
+        ]
+        .iter()
+        {
+            assert!(Grid::from_csv(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn permute_relabels_notes() {
+        let (mut state, (i, j)) = marked_state();
+        state.permute(&[2, 3, 4, 5, 6, 7, 8, 9, 1]);
+        assert_eq!(state.notes[i][j], 1 << 5 | 1 << 8);
+        assert!(state.squares[i][j].is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use super::generator::Difficulty;
+use super::logic::{LogicalSolver, Technique};
+use super::Grid;
+
+/// Score given to puzzles the logical solver can't finish
+const BEYOND_TECHNIQUES: f32 = 6.0;
+
+/// Puzzles solved with hidden singles alone are split
+/// by how many of them it takes
+const VERY_EASY_MAX_STEPS: usize = 45;
+const EASY_MAX_STEPS: usize = 49;
+
+/// Added to the score for every step, so that among puzzles needing
+/// the same techniques the longer solves score higher
+const STEP_SCORE: f32 = 0.005;
+
+/// How hard a puzzle is to solve by hand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    /// Score of the hardest technique needed, on a scale similar
+    /// to Sudoku Explainer's, plus STEP_SCORE for every step taken,
+    /// or BEYOND_TECHNIQUES if the known techniques weren't enough
+    pub score: f32,
+    pub hardest: Option<Technique>,
+    pub steps: usize,
+    pub solved: bool,
+}
+
+impl Rating {
+    pub fn difficulty(&self) -> Difficulty {
+        match self.hardest {
+            _ if !self.solved => Difficulty::Fiendish,
+            Some(technique) if technique > Technique::HiddenPair => Difficulty::Fiendish,
+            Some(technique) if technique > Technique::NakedSingle => Difficulty::Hard,
+            Some(Technique::NakedSingle) => Difficulty::Medium,
+            _ if self.steps <= VERY_EASY_MAX_STEPS => Difficulty::VeryEasy,
+            _ if self.steps <= EASY_MAX_STEPS => Difficulty::Easy,
+            _ => Difficulty::Medium,
+        }
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.2} {}", self.score, self.difficulty())?;
+        match self.hardest {
+            Some(technique) if self.solved => write!(f, " ({}, {} steps)", technique, self.steps),
+            _ if self.solved => write!(f, " ({} steps)", self.steps),
+            _ => write!(f, " (unsolved after {} steps)", self.steps),
+        }
+    }
+}
+
+pub fn score(technique: Technique) -> f32 {
+    match technique {
+        Technique::HiddenSingle => 1.5,
+        Technique::NakedSingle => 2.3,
+        Technique::PointingPair => 2.6,
+        Technique::BoxLineReduction => 2.8,
+        Technique::NakedPair => 3.0,
+        Technique::XWing => 3.2,
+        Technique::HiddenPair => 3.4,
+        Technique::NakedTriple => 3.6,
+        Technique::Swordfish => 3.8,
+        Technique::HiddenTriple => 4.0,
+        Technique::XYWing => 4.2,
+        Technique::SimpleColoring => 4.5,
+    }
+}
+
+/// Rate the puzzle given by the grid's initial squares
+/// by solving it with the logical solver
+pub fn rate(grid: &Grid) -> Rating {
+    rate_values(&grid.clues())
+}
+
+pub fn rate_values(values: &[[u8; 9]; 9]) -> Rating {
+    let mut solver = LogicalSolver::new(values);
+    let steps = solver.solve();
+    let solved = solver.is_solved();
+    let hardest = steps.iter().map(|step| step.technique).max();
+
+    let score = if !solved {
+        BEYOND_TECHNIQUES
+    } else {
+        hardest.map_or(0.0, score) + steps.len() as f32 * STEP_SCORE
+    };
+
+    Rating {
+        score,
+        hardest,
+        steps: steps.len(),
+        solved,
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Grid, GridState, Square, Theme};
+
+/// Most undo and most redo states kept in a save,
+/// so saves stay small however long a game goes on
+const SAVED_HISTORY: usize = 100;
+
+impl Grid {
+    /// The board with its undo and redo history as plain text,
+    /// one state per line after the solution, for saving games.
+    /// History that only moved the cursor is left out
+    pub fn encode(&self) -> String {
+        let solution: String = self
+            .state
+            .solution
+            .iter()
+            .flatten()
+            .map(|&value| (b'0' + value) as char)
+            .collect();
+        let mut lines = vec![
+            format!("solution {solution}"),
+            format!("state {}", self.state.encode()),
+        ];
+        lines.extend(
+            board_changes(&self.past, &self.state)
+                .iter()
+                .map(|state| format!("past {}", state.encode())),
+        );
+        lines.extend(
+            board_changes(&self.future, &self.state)
+                .iter()
+                .map(|state| format!("future {}", state.encode())),
+        );
+        lines.join("\n")
+    }
+
+    pub fn decode(text: &str) -> Result<Grid, String> {
+        let mut solution = None;
+        let mut state = None;
+        let mut past = vec![];
+        let mut future = vec![];
+
+        for line in text.lines() {
+            let invalid = || format!("Invalid line in saved grid: {line}");
+            let (kind, fields) = line.split_once(' ').ok_or_else(invalid)?;
+            if kind == "solution" {
+                solution = Some(decode_solution(fields).ok_or_else(invalid)?);
+                continue;
+            }
+
+            let solution = solution.ok_or("Saved grid has no solution before its states")?;
+            let decoded = GridState::decode(fields, solution)?;
+            match kind {
+                "state" => state = Some(decoded),
+                "past" => past.push(decoded),
+                "future" => future.push(decoded),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Grid {
+            state: state.ok_or("Saved grid has no current state")?,
+            past,
+            future,
+            auto_prune: false,
+            highlighted: HashSet::new(),
+            checked: HashMap::new(),
+            theme: Theme::default(),
+            selected_digit: None,
+        })
+    }
+}
+
+/// The states of an undo or redo stack that change the board from the
+/// state before them, starting from `current`, keeping the nearest
+/// SAVED_HISTORY of them in the stack's order
+fn board_changes<'a>(states: &'a [GridState], current: &GridState) -> Vec<&'a GridState> {
+    let mut kept = vec![];
+    let mut next = current;
+    for state in states.iter().rev() {
+        if state.squares != next.squares || state.notes != next.notes {
+            kept.push(state);
+        }
+        next = state;
+    }
+    kept.truncate(SAVED_HISTORY);
+    kept.reverse();
+    kept
+}
+
+fn decode_solution(digits: &str) -> Option<[[u8; 9]; 9]> {
+    if digits.len() != 81 || !digits.bytes().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let mut solution = [[0; 9]; 9];
+    for (idx, ch) in digits.bytes().enumerate() {
+        solution[idx / 9][idx % 9] = ch - b'0';
+    }
+    Some(solution)
+}
+
+impl GridState {
+    /// Space separated fields: the squares ('.' for empty, '1'-'9' for
+    /// initial squares and 'a'-'i' for filled ones), the notes as 3 hex
+    /// digits per square and the cursor. The solution is saved once for
+    /// the whole grid
+    fn encode(&self) -> String {
+        let mut squares = String::with_capacity(81);
+        let mut notes = String::with_capacity(81 * 3);
+
+        for i in 0..9 {
+            for j in 0..9 {
+                squares.push(match self.squares[i][j] {
+                    Square::Empty => '.',
+                    Square::Initial(value) => (b'0' + value) as char,
+                    Square::Filled(value) => (b'a' + value - 1) as char,
+                });
+                notes.push_str(&format!("{:03x}", self.notes[i][j]));
+            }
+        }
+
+        let (i, j) = self.current;
+        format!("{squares} {notes} {i}{j}")
+    }
+
+    fn decode(line: &str, solution: [[u8; 9]; 9]) -> Result<GridState, String> {
+        let invalid = || format!("Invalid saved grid state: {line}");
+
+        let fields: Vec<&str> = line.split(' ').collect();
+        let &[squares, notes, current] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        if squares.len() != 81 || notes.len() != 81 * 3 || current.len() != 2 || !line.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut state = GridState {
+            squares: [[Square::Empty; 9]; 9],
+            notes: [[0; 9]; 9],
+            solution,
+            current: (0, 0),
+        };
+
+        for (idx, ch) in squares.bytes().enumerate() {
+            state.squares[idx / 9][idx % 9] = match ch {
+                b'.' => Square::Empty,
+                b'1'..=b'9' => Square::Initial(ch - b'0'),
+                b'a'..=b'i' => Square::Filled(ch - b'a' + 1),
+                _ => return Err(invalid()),
+            };
+        }
+
+        for idx in 0..81 {
+            let note =
+                u16::from_str_radix(&notes[idx * 3..idx * 3 + 3], 16).map_err(|_| invalid())?;
+            state.notes[idx / 9][idx % 9] = note;
+        }
+
+        let current = current.as_bytes();
+        if !current.iter().all(|ch| (b'0'..=b'8').contains(ch)) {
+            return Err(invalid());
+        }
+        state.current = ((current[0] - b'0') as usize, (current[1] - b'0') as usize);
+
+        Ok(state)
+    }
+}
@@ -0,0 +1,237 @@
+use fastrand::Rng;
+
+use std::str::FromStr;
+
+use super::dlx::DancingLinks;
+
+/// Bitmask with one bit set for each of the digits 1 to 9
+pub const ALL_DIGITS: u16 = 0b11_1111_1110;
+
+/// A strategy for solving puzzles given as rows of values,
+/// with 0 meaning an empty square
+pub trait Solver {
+    /// Find up to `limit` distinct solutions of the puzzle
+    fn solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> Vec<[[u8; 9]; 9]>;
+
+    /// Return the completed board, or None if there is no solution
+    fn solve(&self, values: &[[u8; 9]; 9]) -> Option<[[u8; 9]; 9]> {
+        self.solutions(values, 1).pop()
+    }
+
+    /// Count the solutions, stopping as soon as `limit` of them have been found
+    fn count_solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> usize {
+        self.solutions(values, limit).len()
+    }
+}
+
+/// Depth first search over candidate bitmasks,
+/// always branching on the most constrained cell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Backtracking;
+
+impl Solver for Backtracking {
+    fn solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> Vec<[[u8; 9]; 9]> {
+        let mut solutions = vec![];
+        if limit == 0 {
+            return solutions;
+        }
+        if let Some(mut board) = Board::new(values) {
+            board.search(&mut |board| {
+                solutions.push(board.values());
+                solutions.len() >= limit
+            });
+        }
+        solutions
+    }
+
+    fn count_solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> usize {
+        let mut count = 0;
+        if limit == 0 {
+            return count;
+        }
+        if let Some(mut board) = Board::new(values) {
+            board.search(&mut |_| {
+                count += 1;
+                count >= limit
+            });
+        }
+        count
+    }
+}
+
+/// The solvers that can be picked by name, e.g. on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Backtracking,
+    DancingLinks,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Backend, String> {
+        match s {
+            "backtracking" => Ok(Backend::Backtracking),
+            "dlx" => Ok(Backend::DancingLinks),
+            _ => Err(format!("Unknown solver: {s}")),
+        }
+    }
+}
+
+impl Solver for Backend {
+    fn solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> Vec<[[u8; 9]; 9]> {
+        match *self {
+            Backend::Backtracking => Backtracking.solutions(values, limit),
+            Backend::DancingLinks => DancingLinks.solutions(values, limit),
+        }
+    }
+
+    fn count_solutions(&self, values: &[[u8; 9]; 9], limit: usize) -> usize {
+        match *self {
+            Backend::Backtracking => Backtracking.count_solutions(values, limit),
+            Backend::DancingLinks => DancingLinks.count_solutions(values, limit),
+        }
+    }
+}
+
+/// A random completely filled board
+pub fn random_solution(rng: &mut Rng) -> [[u8; 9]; 9] {
+    let mut board = Board::new(&[[0; 9]; 9]).unwrap();
+    board.fill_randomly(rng);
+    board.values()
+}
+
+struct Board {
+    cells: [u8; 81],
+    rows: [u16; 9],
+    cols: [u16; 9],
+    blocks: [u16; 9],
+}
+
+impl Board {
+    /// Returns None if the given values already contradict each other
+    fn new(values: &[[u8; 9]; 9]) -> Option<Board> {
+        let mut board = Board {
+            cells: [0; 81],
+            rows: [0; 9],
+            cols: [0; 9],
+            blocks: [0; 9],
+        };
+
+        for (i, row) in values.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if value == 0 {
+                    continue;
+                }
+                if board.candidates(i * 9 + j) & (1 << value) == 0 {
+                    return None;
+                }
+                board.place(i * 9 + j, value);
+            }
+        }
+
+        Some(board)
+    }
+
+    fn values(&self) -> [[u8; 9]; 9] {
+        let mut values = [[0; 9]; 9];
+        for (idx, &value) in self.cells.iter().enumerate() {
+            values[idx / 9][idx % 9] = value;
+        }
+        values
+    }
+
+    fn candidates(&self, idx: usize) -> u16 {
+        let (i, j) = (idx / 9, idx % 9);
+        !(self.rows[i] | self.cols[j] | self.blocks[block_index(i, j)]) & ALL_DIGITS
+    }
+
+    fn place(&mut self, idx: usize, value: u8) {
+        let (i, j) = (idx / 9, idx % 9);
+        let bit = 1 << value;
+        self.cells[idx] = value;
+        self.rows[i] |= bit;
+        self.cols[j] |= bit;
+        self.blocks[block_index(i, j)] |= bit;
+    }
+
+    fn remove(&mut self, idx: usize) {
+        let (i, j) = (idx / 9, idx % 9);
+        let bit = !(1 << self.cells[idx]);
+        self.cells[idx] = 0;
+        self.rows[i] &= bit;
+        self.cols[j] &= bit;
+        self.blocks[block_index(i, j)] &= bit;
+    }
+
+    /// Find the empty cell with the fewest candidates,
+    /// or None if the board is already full
+    fn most_constrained(&self) -> Option<(usize, u16)> {
+        let mut best = None;
+        let mut best_count = 10;
+
+        for idx in 0..81 {
+            if self.cells[idx] != 0 {
+                continue;
+            }
+            let candidates = self.candidates(idx);
+            let count = candidates.count_ones();
+            if count < best_count {
+                best = Some((idx, candidates));
+                best_count = count;
+                if count <= 1 {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Call `visit` with every completed board until it returns true.
+    /// Returns whether the search was stopped early
+    fn search<F: FnMut(&Board) -> bool>(&mut self, visit: &mut F) -> bool {
+        let Some((idx, candidates)) = self.most_constrained() else {
+            return visit(self);
+        };
+
+        for value in 1..10 {
+            if candidates & (1 << value) == 0 {
+                continue;
+            }
+            self.place(idx, value);
+            let stop = self.search(visit);
+            self.remove(idx);
+            if stop {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Fill the remaining cells trying candidates in random order,
+    /// leaving the board solved if possible
+    fn fill_randomly(&mut self, rng: &mut Rng) -> bool {
+        let Some((idx, candidates)) = self.most_constrained() else {
+            return true;
+        };
+
+        let mut values: Vec<u8> = (1..10).filter(|v| candidates & (1 << v) != 0).collect();
+        rng.shuffle(&mut values);
+
+        for value in values {
+            self.place(idx, value);
+            if self.fill_randomly(rng) {
+                return true;
+            }
+            self.remove(idx);
+        }
+
+        false
+    }
+}
+
+fn block_index(i: usize, j: usize) -> usize {
+    (i / 3) * 3 + j / 3
+}
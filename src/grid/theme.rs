@@ -0,0 +1,109 @@
+use termion::color::Rgb;
+
+use std::fs;
+
+use storage;
+
+const THEME_FILE: &str = "theme";
+
+/// A background highlight that can be switched on and off while playing
+#[derive(Debug, Clone, Copy)]
+pub struct Layer {
+    pub color: Rgb,
+    pub enabled: bool,
+}
+
+impl Layer {
+    fn new(color: Rgb) -> Layer {
+        Layer {
+            color,
+            enabled: true,
+        }
+    }
+}
+
+/// Background colors used to highlight squares on the board
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Squares pointed out by a hint
+    pub hint: Rgb,
+    /// Squares holding the selected digit
+    pub same_digit: Layer,
+    /// The row, column and block of the cursor
+    pub peers: Layer,
+    /// Empty squares with the selected digit in their notes
+    pub candidates: Layer,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            hint: Rgb(60, 60, 100),
+            same_digit: Layer::new(Rgb(90, 80, 30)),
+            peers: Layer::new(Rgb(40, 40, 40)),
+            candidates: Layer::new(Rgb(30, 70, 50)),
+        }
+    }
+}
+
+impl Theme {
+    /// The theme from the config directory, falling back
+    /// to the default for anything it doesn't set
+    pub fn load() -> Theme {
+        let contents = storage::config_file(THEME_FILE)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        Theme::parse(&contents)
+    }
+
+    /// Parse lines like `same-digit #5a501e` or `peers off`,
+    /// ignoring blank lines, `//` comments and anything unrecognised
+    pub fn parse(text: &str) -> Theme {
+        let mut theme = Theme::default();
+
+        for line in text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+        {
+            let mut parts = line.split_whitespace();
+            let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+
+            if name == "hint" {
+                if let Some(color) = parse_color(value) {
+                    theme.hint = color;
+                }
+                continue;
+            }
+
+            let layer = match name {
+                "same-digit" => &mut theme.same_digit,
+                "peers" => &mut theme.peers,
+                "candidates" => &mut theme.candidates,
+                _ => continue,
+            };
+            match value {
+                "on" => layer.enabled = true,
+                "off" => layer.enabled = false,
+                _ => {
+                    if let Some(color) = parse_color(value) {
+                        layer.color = color;
+                    }
+                }
+            }
+        }
+
+        theme
+    }
+}
+
+/// Parse a color written like `#5a501e`
+fn parse_color(hex: &str) -> Option<Rgb> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |k: usize| u8::from_str_radix(&hex[k * 2..k * 2 + 2], 16).ok();
+    Some(Rgb(channel(0)?, channel(1)?, channel(2)?))
+}
@@ -0,0 +1,92 @@
+use grid::{BOARD_HEIGHT, BOARD_WIDTH};
+
+/// Width of the remaining-digit panel beside the board
+pub const PANEL_WIDTH: u16 = 14;
+/// Space between the board and the panel
+const GAP: u16 = 3;
+/// Lines under the board for the status and the message
+const FOOTER_HEIGHT: u16 = 2;
+
+/// A position on the terminal, with (1, 1) being the top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Where each part of the game screen is drawn, centered as a whole
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    /// Top left corner of the board
+    pub board: Point,
+    /// Top left corner of the panel, None if the terminal is too narrow for it
+    pub panel: Option<Point>,
+    /// Start of the status line under the board
+    pub status: Point,
+    /// Start of the message line under the status
+    pub message: Point,
+}
+
+impl Layout {
+    pub fn new(width: u16, height: u16) -> Layout {
+        let with_panel = BOARD_WIDTH + GAP + PANEL_WIDTH;
+        let (total_width, has_panel) = if width >= with_panel {
+            (with_panel, true)
+        } else {
+            (BOARD_WIDTH, false)
+        };
+
+        let top = height.saturating_sub(BOARD_HEIGHT + FOOTER_HEIGHT) / 2 + 1;
+        let left = width.saturating_sub(total_width) / 2 + 1;
+
+        Layout {
+            board: Point { x: left, y: top },
+            panel: if has_panel {
+                Some(Point {
+                    x: left + BOARD_WIDTH + GAP,
+                    y: top,
+                })
+            } else {
+                None
+            },
+            status: Point {
+                x: left,
+                y: top + BOARD_HEIGHT,
+            },
+            message: Point {
+                x: left,
+                y: top + BOARD_HEIGHT + 1,
+            },
+        }
+    }
+
+    /// The square drawn at the given position, if any
+    pub fn square_at(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        // Skip the outer border, then every square is followed by a border line
+        let row = y.checked_sub(self.board.y + 1)?;
+        let col = x.checked_sub(self.board.x + 1)?;
+        if row % 4 == 3 || col % 6 == 5 || row / 4 >= 9 || col / 6 >= 9 {
+            return None;
+        }
+        Some(((row / 4) as usize, (col / 6) as usize))
+    }
+
+    /// Position of the panel line for `digit`, laid out
+    /// next to the middle of the board's rows
+    pub fn panel_digit(&self, digit: u8) -> Option<Point> {
+        let panel = self.panel?;
+        Some(Point {
+            x: panel.x,
+            y: panel.y + 2 + (digit as u16 - 1) * 4,
+        })
+    }
+
+    /// The digit whose panel line is at the given position, if any
+    pub fn panel_digit_at(&self, x: u16, y: u16) -> Option<u8> {
+        let panel = self.panel?;
+        if x < panel.x || x >= panel.x + PANEL_WIDTH {
+            return None;
+        }
+        (1..10).find(|&d| self.panel_digit(d).map(|p| p.y) == Some(y))
+    }
+}
@@ -3,17 +3,70 @@
 extern crate fastrand;
 extern crate termion;
 
+use std::env;
 use std::io;
+use std::process;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::IntoAlternateScreen;
 
+mod cli;
+mod clock;
+mod commands;
+mod daily;
+mod format;
 mod grid;
+mod layout;
+mod save;
+mod stats;
+mod storage;
 
 mod game;
 use game::Game;
 
+use cli::Command;
+use grid::generator::PuzzleId;
+use grid::solver::Backend;
+use grid::Grid;
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = cli::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{err}\n\n{}", cli::USAGE);
+        process::exit(2);
+    });
+
+    match command {
+        Command::Play {
+            id,
+            difficulty,
+            file,
+        } => {
+            // Read the puzzle before taking over the terminal, so errors can be shown
+            let grid = file.map(|file| {
+                let (_, puzzles) = commands::read_puzzles(Some(file)).unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    process::exit(2);
+                });
+                let Some(&values) = puzzles.first() else {
+                    eprintln!("No puzzle to play");
+                    process::exit(2);
+                };
+                if let Some(problem) = commands::problem(&values, Backend::Backtracking) {
+                    eprintln!("Can't play this puzzle: {problem}");
+                    process::exit(2);
+                }
+                Grid::playable(values)
+            });
+            let id = id.or(difficulty.map(PuzzleId::random));
+            play(id, grid);
+        }
+        Command::Help => print!("{}", cli::USAGE),
+        command => process::exit(commands::run(command)),
+    }
+}
+
+fn play(id: Option<PuzzleId>, grid: Option<Grid>) {
     let stdin = io::stdin();
     let screen = io::stdout()
         .into_raw_mode()
@@ -24,5 +77,9 @@ fn main() {
 
     let mut game = Game::new(stdin, stdout);
 
-    game.run();
+    match (grid, id) {
+        (Some(grid), _) => game.play_grid(grid),
+        (None, Some(id)) => game.play(id),
+        (None, None) => game.run(),
+    }
 }
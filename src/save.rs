@@ -0,0 +1,190 @@
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use daily::Date;
+use grid::generator::{Difficulty, PuzzleId};
+use grid::Grid;
+use storage;
+
+const SAVE_FILE: &str = "saved-game";
+
+/// Everything needed to pick an unfinished game back up
+pub struct SavedGame {
+    /// None for puzzles that didn't come from the generator
+    pub id: Option<PuzzleId>,
+    pub difficulty: Difficulty,
+    /// Set when it's the daily puzzle of that date
+    pub daily: Option<Date>,
+    pub elapsed: Duration,
+    pub moves: usize,
+    pub mistakes: usize,
+    pub hints_used: usize,
+    pub grid: Grid,
+}
+
+impl SavedGame {
+    fn encode(&self) -> String {
+        let mut text = format!("difficulty {}\n", self.difficulty.letter());
+        if let Some(id) = self.id {
+            text.push_str(&format!("id {id}\n"));
+        }
+        if let Some(date) = self.daily {
+            text.push_str(&format!("daily {date}\n"));
+        }
+        text.push_str(&format!("elapsed {}\n", self.elapsed.as_millis()));
+        text.push_str(&format!("moves {}\n", self.moves));
+        text.push_str(&format!("mistakes {}\n", self.mistakes));
+        text.push_str(&format!("hints {}\n", self.hints_used));
+        text.push_str(&self.grid.encode());
+        text
+    }
+
+    fn decode(text: &str) -> Result<SavedGame, String> {
+        let mut id = None;
+        let mut difficulty = None;
+        let mut daily = None;
+        let mut elapsed = Duration::ZERO;
+        let mut moves = 0;
+        let mut mistakes = 0;
+        let mut hints_used = 0;
+        let mut grid_lines = vec![];
+
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let invalid = || format!("Invalid line in saved game: {line}");
+            match key {
+                "id" => id = Some(value.parse::<PuzzleId>()?),
+                "difficulty" => {
+                    let letter = value.chars().next().ok_or_else(invalid)?;
+                    difficulty = Some(Difficulty::from_letter(letter).ok_or_else(invalid)?);
+                }
+                "daily" => daily = Some(value.parse::<Date>()?),
+                "elapsed" => {
+                    elapsed = Duration::from_millis(value.parse().map_err(|_| invalid())?);
+                }
+                "moves" => moves = value.parse().map_err(|_| invalid())?,
+                "mistakes" => mistakes = value.parse().map_err(|_| invalid())?,
+                "hints" => hints_used = value.parse().map_err(|_| invalid())?,
+                _ => grid_lines.push(line),
+            }
+        }
+
+        Ok(SavedGame {
+            id,
+            difficulty: difficulty
+                .or(id.map(|id| id.difficulty))
+                .ok_or("Saved game has no difficulty")?,
+            daily,
+            elapsed,
+            moves,
+            mistakes,
+            hints_used,
+            grid: Grid::decode(&grid_lines.join("\n"))?,
+        })
+    }
+}
+
+pub fn exists() -> bool {
+    storage::data_file(SAVE_FILE).is_ok_and(|path| path.exists())
+}
+
+/// The saved game, or None if there isn't one or it can't be read
+pub fn load() -> Option<SavedGame> {
+    let contents = fs::read_to_string(storage::data_file(SAVE_FILE).ok()?).ok()?;
+    SavedGame::decode(&contents).ok()
+}
+
+/// Replace the saved game with this one
+pub fn store(game: &SavedGame) -> io::Result<()> {
+    let path = storage::data_file(SAVE_FILE)?;
+    // Write to a temporary file first so a crash can't leave half a save behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, game.encode())?;
+    fs::rename(tmp, path)
+}
+
+pub fn remove() -> io::Result<()> {
+    let path = storage::data_file(SAVE_FILE)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grid::generator::Generator;
+    use grid::Direction;
+
+    fn played_game() -> SavedGame {
+        let id = PuzzleId {
+            difficulty: Difficulty::Easy,
+            seed: 0x3f9a2,
+        };
+        let mut grid = Generator::from_id(id);
+        let empty = (0..81)
+            .map(|idx| (idx / 9, idx % 9))
+            .find(|&(i, j)| grid.values()[i][j] == 0)
+            .unwrap();
+        grid.move_cursor_to(empty.0, empty.1);
+        grid.toggle_note(3);
+        grid.toggle_note(5);
+        grid.update_current(grid.solution().unwrap()[empty.0][empty.1] as usize);
+        grid.move_cursor(Direction::Right);
+        grid.move_cursor(Direction::Down);
+        grid.undo();
+
+        SavedGame {
+            id: Some(id),
+            difficulty: Difficulty::Easy,
+            daily: Some("2026-10-18".parse().unwrap()),
+            elapsed: Duration::from_millis(83_250),
+            moves: 4,
+            mistakes: 1,
+            hints_used: 2,
+            grid,
+        }
+    }
+
+    #[test]
+    fn saved_games_round_trip() {
+        let game = played_game();
+        let decoded = SavedGame::decode(&game.encode()).unwrap();
+
+        assert_eq!(decoded.id, game.id);
+        assert_eq!(decoded.difficulty, game.difficulty);
+        assert_eq!(decoded.daily, game.daily);
+        assert_eq!(decoded.elapsed, game.elapsed);
+        assert_eq!(decoded.moves, game.moves);
+        assert_eq!(decoded.mistakes, game.mistakes);
+        assert_eq!(decoded.hints_used, game.hints_used);
+        assert_eq!(decoded.grid.values(), game.grid.values());
+        assert_eq!(decoded.grid.current(), game.grid.current());
+        assert_eq!(decoded.grid.solution(), game.grid.solution());
+        assert_eq!(decoded.grid.encode(), game.grid.encode());
+        assert_eq!(decoded.encode(), game.encode());
+    }
+
+    #[test]
+    fn cursor_moves_dont_grow_saves() {
+        let mut game = played_game();
+        let size = game.encode().len();
+        for _ in 0..50 {
+            game.grid.move_cursor(Direction::Left);
+        }
+        assert_eq!(game.encode().len(), size);
+        assert_eq!(game.encode().matches("solution").count(), 1);
+    }
+
+    #[test]
+    fn grids_need_their_solution() {
+        let encoded = played_game().grid.encode();
+        let without: Vec<&str> = encoded
+            .lines()
+            .filter(|line| !line.starts_with("solution "))
+            .collect();
+        assert!(Grid::decode(&without.join("\n")).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use grid::generator::Difficulty;
+use storage;
+
+/// Log of games played, one line per game started or completed,
+/// like "H start" and "H win 312500 2" (time in milliseconds, hints used)
+const STATS_FILE: &str = "stats";
+
+/// Totals over every game of one difficulty
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub started: usize,
+    pub completed: usize,
+    pub best: Option<Duration>,
+    pub total_time: Duration,
+    pub hints_used: usize,
+    /// Games completed in a row, ending with the latest one
+    pub streak: usize,
+}
+
+impl Stats {
+    pub fn average(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            None
+        } else {
+            Some(self.total_time / self.completed as u32)
+        }
+    }
+}
+
+/// Statistics for the given difficulty
+pub fn load(difficulty: Difficulty) -> Stats {
+    let mut stats = Stats::default();
+    let Ok(path) = storage::data_file(STATS_FILE) else {
+        return stats;
+    };
+    let Ok(contents) = fs::read_to_string(path) else {
+        return stats;
+    };
+
+    // Whether the latest game started has been completed yet.
+    // Starting another before that gives up on it, ending the streak
+    let mut open = false;
+    for line in contents.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let letter = parts.first().and_then(|part| part.chars().next());
+        if letter.and_then(Difficulty::from_letter) != Some(difficulty) {
+            continue;
+        }
+
+        match parts.as_slice() {
+            [_, "start"] => {
+                if open {
+                    stats.streak = 0;
+                }
+                stats.started += 1;
+                open = true;
+            }
+            [_, "win", millis, hints] => {
+                let (Ok(millis), Ok(hints)) = (millis.parse(), hints.parse::<usize>()) else {
+                    continue;
+                };
+                let time = Duration::from_millis(millis);
+                stats.completed += 1;
+                stats.total_time += time;
+                stats.hints_used += hints;
+                stats.best = Some(stats.best.map_or(time, |best| best.min(time)));
+                stats.streak += 1;
+                open = false;
+            }
+            _ => {}
+        }
+    }
+
+    stats
+}
+
+pub fn record_started(difficulty: Difficulty) -> io::Result<()> {
+    append(&format!("{} start", difficulty.letter()))
+}
+
+pub fn record_completed(
+    difficulty: Difficulty,
+    time: Duration,
+    hints_used: usize,
+) -> io::Result<()> {
+    append(&format!(
+        "{} win {} {}",
+        difficulty.letter(),
+        time.as_millis(),
+        hints_used
+    ))
+}
+
+fn append(line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(storage::data_file(STATS_FILE)?)?;
+    writeln!(file, "{line}")
+}
@@ -0,0 +1,35 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Directory for persistent data, following the XDG base directory spec
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join("sudoku"))
+}
+
+/// Directory for user configuration, following the XDG base directory spec
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("sudoku"))
+}
+
+/// Path of a file in the config directory, which may not exist
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    Some(config_dir()?.join(name))
+}
+
+/// Path of a file in the data directory, creating the directory if needed
+pub fn data_file(name: &str) -> io::Result<PathBuf> {
+    let dir =
+        data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory"))?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}